@@ -0,0 +1,206 @@
+//! C and C++ projects, built with CMake or Meson.
+
+use crate::options::Flag;
+use crate::plan::Plan;
+use crate::project::{create_readme, Metadata, Readme};
+use crate::MakeProjectError;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BuildSystem {
+    CMake,
+    Meson,
+}
+
+impl BuildSystem {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BuildSystem::CMake => "cmake",
+            BuildSystem::Meson => "meson",
+        }
+    }
+}
+
+impl std::fmt::Display for BuildSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BuildSystem {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cmake" => Ok(BuildSystem::CMake),
+            "meson" => Ok(BuildSystem::Meson),
+            o => Err(MakeProjectError::ArgumentError(format!(
+                "unknown build system: `{}`",
+                o
+            ))),
+        }
+    }
+}
+
+/// Options for C and C++ projects
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct COptions {
+    /// Build system to generate files for: cmake or meson (c, cpp)
+    #[structopt(long = "build-system", raw(global = "true"))]
+    pub build_system: Option<BuildSystem>,
+}
+
+impl COptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[("--build-system", |o| o.c.build_system.is_some())];
+
+    pub fn build_system(&self) -> BuildSystem {
+        self.build_system.unwrap_or(BuildSystem::CMake)
+    }
+}
+
+fn cmake_lists(name: &str, cpp: bool) -> String {
+    let (language, standard_var, standard, source) = if cpp {
+        ("CXX", "CMAKE_CXX_STANDARD", "17", "src/main.cpp")
+    } else {
+        ("C", "CMAKE_C_STANDARD", "11", "src/main.c")
+    };
+
+    format!(
+        r#"cmake_minimum_required(VERSION 3.10)
+project({name} LANGUAGES {language})
+
+set({var} {standard})
+set({var}_REQUIRED ON)
+
+add_executable({name} {source})
+target_include_directories({name} PRIVATE include)
+"#,
+        name = name,
+        language = language,
+        var = standard_var,
+        standard = standard,
+        source = source,
+    )
+}
+
+fn meson_build(name: &str, cpp: bool) -> String {
+    let (language, standard, source) = if cpp {
+        ("cpp", "cpp_std=c++17", "src/main.cpp")
+    } else {
+        ("c", "c_std=c11", "src/main.c")
+    };
+
+    format!(
+        r#"project('{name}', '{language}',
+  version : '0.1.0',
+  default_options : ['warning_level=3', '{standard}'])
+
+inc = include_directories('include')
+executable('{name}', '{source}', include_directories : inc)
+"#,
+        name = name,
+        language = language,
+        standard = standard,
+        source = source,
+    )
+}
+
+const C_MAIN: &str = r#"#include <stdio.h>
+
+int main(void) {
+    printf("Hello, world!\n");
+    return 0;
+}
+"#;
+
+const CPP_MAIN: &str = r#"#include <iostream>
+
+int main() {
+    std::cout << "Hello, world!" << std::endl;
+    return 0;
+}
+"#;
+
+pub(crate) fn c_project_plan(meta: &Metadata, cpp: bool, options: &COptions) -> Plan {
+    let name = meta.name.to_string_lossy();
+
+    let mut plan = Plan::new();
+    plan.create_dir("");
+    match options.build_system() {
+        BuildSystem::CMake => plan.write_file("CMakeLists.txt", cmake_lists(&name, cpp)),
+        BuildSystem::Meson => plan.write_file("meson.build", meson_build(&name, cpp)),
+    }
+    plan.create_dir("include");
+    plan.create_dir("src");
+    if cpp {
+        plan.write_file("src/main.cpp", CPP_MAIN);
+    } else {
+        plan.write_file("src/main.c", C_MAIN);
+    }
+    let install = match options.build_system() {
+        BuildSystem::CMake => vec!["cmake -B build", "cmake --build build"],
+        BuildSystem::Meson => vec!["meson setup build", "meson compile -C build"],
+    };
+    let readme = Readme {
+        install: install.into_iter().map(String::from).collect(),
+        usage: vec![format!("./build/{}", name)],
+        ..Default::default()
+    };
+    create_readme(&mut plan, meta, readme);
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::compute_project_name;
+    use std::fs;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn create_c_project(
+        path: &Path,
+        cpp: bool,
+        options: &COptions,
+    ) -> Result<(), MakeProjectError> {
+        c_project_plan(&Metadata::new(compute_project_name(path)), cpp, options).execute(path)
+    }
+
+    #[test]
+    fn creating_a_c_project() {
+        let temp_dir = TempDir::new("mkproject-c-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        create_c_project(&path, false, &COptions::default()).expect("creating C project");
+
+        assert!(path.join("CMakeLists.txt").is_file());
+        assert!(path.join("src").join("main.c").is_file());
+        assert!(path.join("include").is_dir());
+        assert!(path.join("README.md").is_file());
+
+        let cmake = fs::read_to_string(path.join("CMakeLists.txt")).unwrap();
+        assert!(cmake.contains("project(myproject LANGUAGES C)"));
+        assert!(cmake.contains("add_executable(myproject src/main.c)"));
+    }
+
+    #[test]
+    fn creating_a_cpp_project_with_meson() {
+        let temp_dir = TempDir::new("mkproject-cpp-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let options = COptions {
+            build_system: Some(BuildSystem::Meson),
+        };
+
+        create_c_project(&path, true, &options).expect("creating C++ project");
+
+        assert!(path.join("meson.build").is_file());
+        assert!(!path.join("CMakeLists.txt").exists());
+        assert!(path.join("src").join("main.cpp").is_file());
+        assert!(path.join("include").is_dir());
+
+        let meson = fs::read_to_string(path.join("meson.build")).unwrap();
+        assert!(meson.starts_with("project('myproject', 'cpp',"));
+        assert!(meson.contains("'src/main.cpp'"));
+    }
+}
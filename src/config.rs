@@ -0,0 +1,184 @@
+//! The global configuration file, `~/.config/mkproject/config.toml`, which
+//! holds defaults for the command line options.
+
+use crate::MakeProjectError;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub language: Option<String>,
+    pub author: Option<String>,
+    pub email: Option<String>,
+    pub license: Option<String>,
+    pub vcs: Option<String>,
+    pub commit: Option<bool>,
+    pub commit_message: Option<String>,
+    pub badges: Option<bool>,
+    pub pre_create: Option<Vec<String>>,
+    pub post_create: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub rust: RustConfig,
+
+    #[serde(default)]
+    pub python: PythonConfig,
+
+    #[serde(default)]
+    pub node: NodeConfig,
+
+    #[serde(default)]
+    pub c: CConfig,
+
+    /// Where the configuration was read from, if anywhere
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RustConfig {
+    pub lib: Option<bool>,
+    pub edition: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    pub dev_dependencies: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PythonConfig {
+    pub python: Option<String>,
+    pub env: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    pub dev_dependencies: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeConfig {
+    pub install: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CConfig {
+    pub build_system: Option<String>,
+}
+
+impl Config {
+    /// Read the configuration at `path`
+    pub fn load(path: &Path) -> Result<Config, MakeProjectError> {
+        let text = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| MakeProjectError::Config(format!("reading {}: {}", path.display(), e)))?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Read the configuration at `path`, or from the default location if no
+    /// path is given. A missing default configuration file is not an error.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Config, MakeProjectError> {
+        if let Some(path) = path {
+            return Config::load(path);
+        }
+
+        match default_config_path() {
+            Some(path) if path.is_file() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/mkproject` or `~/.config/mkproject`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("mkproject"))
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    CommandLine,
+    ConfigFile(PathBuf),
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::CommandLine => write!(f, "command line"),
+            Source::ConfigFile(path) => write!(f, "{}", path.display()),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// A single effective setting, as shown by `mkproject config show`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub value: String,
+    pub source: Source,
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {:?} ({})", self.key, self.value, self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn loading_a_config_file() {
+        let temp_dir = TempDir::new("mkproject-config").unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"
+language = "rust"
+author = "Jo Bloggs"
+
+[rust]
+edition = "2018"
+
+[python]
+python = "python3.7"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.language.as_deref(), Some("rust"));
+        assert_eq!(config.author.as_deref(), Some("Jo Bloggs"));
+        assert_eq!(config.email, None);
+        assert_eq!(config.rust.edition.as_deref(), Some("2018"));
+        assert_eq!(config.python.python.as_deref(), Some("python3.7"));
+        assert_eq!(config.path, Some(path));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let temp_dir = TempDir::new("mkproject-config").unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "langauge = \"rust\"\n").unwrap();
+
+        match Config::load(&path) {
+            Err(MakeProjectError::Config(msg)) => assert!(msg.contains("langauge")),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Checks of the programs a project needs, made before anything is created
+//! and by `mkproject doctor`.
+
+use crate::python_env::{self, Interpreter};
+use crate::{Edition, Language, MakeProjectError, Options, PythonEnv, Vcs};
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+/// A program needed to create a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tool {
+    program: String,
+    /// Arguments that make the program print its version, none if it is only
+    /// looked for on `PATH`
+    version_args: Vec<String>,
+    /// Oldest usable version, with what needs it
+    minimum: Option<(&'static str, &'static str)>,
+    /// Arguments that must run successfully, with the problem when they don't
+    probe: Option<(Vec<String>, String)>,
+}
+
+impl Tool {
+    pub fn new<S: Into<String>>(program: S) -> Tool {
+        Tool {
+            program: program.into(),
+            version_args: Vec::new(),
+            minimum: None,
+            probe: None,
+        }
+    }
+
+    fn version_args(mut self, args: &[&str]) -> Tool {
+        self.version_args = args.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    fn minimum(mut self, version: &'static str, needed_for: &'static str) -> Tool {
+        self.minimum = Some((version, needed_for));
+        self
+    }
+
+    fn probe(mut self, args: &[&str], problem: String) -> Tool {
+        self.probe = Some((args.iter().map(|a| a.to_string()).collect(), problem));
+        self
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Look for the program, then check its version and run its probe
+    pub fn check(&self) -> Check {
+        let status = self.status();
+        Check {
+            program: self.program.clone(),
+            status,
+        }
+    }
+
+    fn status(&self) -> Status {
+        let found = if self.program.contains('/') {
+            Path::new(&self.program).is_file()
+        } else {
+            find_executable(&self.program).is_some()
+        };
+        if !found {
+            return Status::Missing;
+        }
+
+        let mut version = None;
+        if !self.version_args.is_empty() {
+            let op = match process::Command::new(&self.program)
+                .args(&self.version_args)
+                .output()
+            {
+                Ok(op) => op,
+                Err(e) => return Status::Broken(e.to_string()),
+            };
+            let output = String::from_utf8_lossy(&op.stdout) + String::from_utf8_lossy(&op.stderr);
+            version = parse_version(&output);
+            if !op.status.success() {
+                return Status::Broken(format!("`{}` does not run", self.program));
+            }
+        }
+
+        if let (Some((minimum, needed_for)), Some(found)) = (self.minimum, &version) {
+            if compare_versions(found, minimum) == Ordering::Less {
+                return Status::TooOld {
+                    version: found.clone(),
+                    minimum,
+                    needed_for,
+                };
+            }
+        }
+
+        if let Some((args, problem)) = &self.probe {
+            let works = process::Command::new(&self.program)
+                .args(args)
+                .output()
+                .map(|op| op.status.success())
+                .unwrap_or(false);
+            if !works {
+                return Status::Broken(problem.clone());
+            }
+        }
+        Status::Ok(version)
+    }
+}
+
+/// The outcome of checking a tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    program: String,
+    status: Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    /// Usable, with its version if it was asked for
+    Ok(Option<String>),
+    Missing,
+    TooOld {
+        version: String,
+        minimum: &'static str,
+        needed_for: &'static str,
+    },
+    Broken(String),
+}
+
+impl Check {
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status, Status::Ok(_))
+    }
+
+    /// The version found, if the tool is usable and reports one
+    pub fn version(&self) -> Option<&str> {
+        match &self.status {
+            Status::Ok(version) => version.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.status {
+            Status::Ok(Some(version)) => write!(f, "`{}` {}", self.program, version),
+            Status::Ok(None) => write!(f, "`{}` was found", self.program),
+            Status::Missing => write!(
+                f,
+                "`{}` was not found, is it installed and on your PATH?",
+                self.program
+            ),
+            Status::TooOld {
+                version,
+                minimum,
+                needed_for,
+            } => write!(
+                f,
+                "`{}` {} is too old, {} needs {} or newer",
+                self.program, version, needed_for, minimum
+            ),
+            Status::Broken(problem) => f.write_str(problem),
+        }
+    }
+}
+
+/// The tools needed to create a project in `language`
+pub fn language_tools(language: &Language, options: &Options) -> Vec<Tool> {
+    match language {
+        Language::Python => {
+            let program = Interpreter::program(options.python.interpreter());
+            let mut python = Tool::new(program.as_str()).version_args(&python_env::VERSION_ARGS);
+            if options.python.env() == PythonEnv::Venv {
+                let problem = format!(
+                    "`{}` cannot create virtual environments, install its `venv` module \
+                     (e.g. `apt install python3-venv`)",
+                    program
+                );
+                python = python.probe(&["-c", "import venv, ensurepip"], problem);
+            }
+            let manager = match options.python.env() {
+                PythonEnv::Uv => Some("uv"),
+                PythonEnv::Poetry => Some("poetry"),
+                PythonEnv::Pipenv => Some("pipenv"),
+                PythonEnv::Conda => Some("conda"),
+                PythonEnv::Venv | PythonEnv::None => None,
+            };
+            let mut tools = vec![python];
+            tools.extend(manager.map(|m| Tool::new(m).version_args(&["--version"])));
+            tools
+        }
+        Language::Rust => {
+            let cargo = Tool::new("cargo").version_args(&["--version"]);
+            let cargo = match options.rust.edition {
+                Some(Edition::E2018) => cargo.minimum("1.31", "`--edition 2018`"),
+                Some(Edition::E2021) => cargo.minimum("1.56", "`--edition 2021`"),
+                Some(Edition::E2024) => cargo.minimum("1.85", "`--edition 2024`"),
+                Some(Edition::E2015) | None => cargo,
+            };
+            vec![cargo]
+        }
+        Language::Go => vec![Tool::new("go")
+            .version_args(&["version"])
+            .minimum("1.11", "`go mod init`")],
+        Language::JavaScript | Language::TypeScript if !options.node.no_install => {
+            vec![Tool::new("npm").version_args(&["--version"])]
+        }
+        Language::JavaScript | Language::TypeScript | Language::C | Language::Cpp => Vec::new(),
+        Language::Plugin(plugin) => plugin.programs().into_iter().map(Tool::new).collect(),
+    }
+}
+
+/// The tools needed to set up version control
+pub fn vcs_tools(options: &Options) -> Vec<Tool> {
+    match options.vcs() {
+        Vcs::Git => vec![Tool::new("git").version_args(&["--version"])],
+        Vcs::None => Vec::new(),
+    }
+}
+
+/// Check every tool, reporting all of the problems found together
+pub(crate) fn preflight(tools: &[Tool]) -> Result<(), MakeProjectError> {
+    let problems: Vec<_> = tools
+        .iter()
+        .map(Tool::check)
+        .filter(|check| !check.is_ok())
+        .map(|check| format!("\n  {}", check))
+        .collect();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(MakeProjectError::Process(
+        format!(
+            "required programs are missing or unusable:{}",
+            problems.concat()
+        ),
+        127,
+    ))
+}
+
+/// The first version number in `output`, e.g. `1.22.1` in
+/// `go version go1.22.1 linux/amd64`
+fn parse_version(output: &str) -> Option<String> {
+    output.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let end = word
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(word.len());
+        let version = word[..end].trim_end_matches('.');
+        if version.contains('.') {
+            Some(version.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Compare dotted version numbers, treating missing parts as zero
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Look for an executable called `name` on `PATH`
+pub(crate) fn find_executable(name: &str) -> Option<PathBuf> {
+    find_executable_in(name, &env::var_os("PATH")?)
+}
+
+/// Look for an executable called `name` in `paths`, a list of directories
+/// like `PATH`
+pub(crate) fn find_executable_in(name: &str, paths: &OsStr) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    env::split_paths(paths)
+        .map(|dir| dir.join(name))
+        .find(|path| {
+            fs::metadata(path)
+                .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+    use crate::PythonOptions;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parsing_versions() {
+        let version = |s| parse_version(s);
+        assert_eq!(
+            version("cargo 1.95.0 (f2d3ce0bd 2026-03-21)"),
+            Some("1.95.0".to_string())
+        );
+        assert_eq!(
+            version("go version go1.22.1 linux/amd64"),
+            Some("1.22.1".to_string())
+        );
+        assert_eq!(version("Poetry (version 1.8.2)"), Some("1.8.2".to_string()));
+        assert_eq!(version("v20.1.0\n"), Some("20.1.0".to_string()));
+        assert_eq!(version("no version here 3"), None);
+    }
+
+    #[test]
+    fn comparing_versions() {
+        assert_eq!(compare_versions("1.85.0", "1.85"), Ordering::Equal);
+        assert_eq!(compare_versions("1.9", "1.85"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "1.85"), Ordering::Greater);
+    }
+
+    #[test]
+    fn checking_tools() {
+        let temp_dir = TempDir::new("mkproject-doctor").unwrap();
+        let old = temp_dir.path().join("old");
+        write_stub(&old, "#!/bin/sh\necho old 1.2.3\n");
+        let old = old.to_str().unwrap();
+
+        let check = Tool::new(old).version_args(&["--version"]).check();
+        assert!(check.is_ok());
+        assert_eq!(check.version(), Some("1.2.3"));
+
+        let check = Tool::new(old)
+            .version_args(&["--version"])
+            .minimum("1.10", "`--new`")
+            .check();
+        assert!(!check.is_ok());
+        assert_eq!(
+            check.to_string(),
+            format!("`{}` 1.2.3 is too old, `--new` needs 1.10 or newer", old)
+        );
+
+        let check = Tool::new("mkproject-no-such-tool").check();
+        assert_eq!(
+            check.to_string(),
+            "`mkproject-no-such-tool` was not found, is it installed and on your PATH?"
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported_together() {
+        let temp_dir = TempDir::new("mkproject-doctor").unwrap();
+        let python = temp_dir.path().join("python");
+        write_stub(
+            &python,
+            "#!/bin/sh\n\
+             if [ \"$2\" = \"import venv, ensurepip\" ]; then exit 1; fi\n\
+             echo 3.12.1\n",
+        );
+        let python = python.to_str().unwrap().to_string();
+
+        let options = Options {
+            python: PythonOptions {
+                python: Some(python.clone()),
+                env: Some(PythonEnv::Venv),
+            },
+            ..Default::default()
+        };
+        let mut tools = language_tools(&Language::Python, &options);
+        tools.push(Tool::new("mkproject-no-such-tool"));
+        match preflight(&tools) {
+            Err(MakeProjectError::Process(msg, 127)) => assert_eq!(
+                msg,
+                format!(
+                    "required programs are missing or unusable:\n  \
+                     `{}` cannot create virtual environments, install its `venv` module \
+                     (e.g. `apt install python3-venv`)\n  \
+                     `mkproject-no-such-tool` was not found, is it installed and on your PATH?",
+                    python
+                )
+            ),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Go projects, created with `go mod init`.
+
+use crate::options::Flag;
+use crate::plan::{Command, Plan};
+use crate::project::{create_readme, Metadata, Readme};
+use structopt::StructOpt;
+
+/// Options controlling `go mod init`
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct GoOptions {
+    /// Module path, defaults to the project name (go)
+    #[structopt(long = "module", raw(global = "true"))]
+    pub module: Option<String>,
+}
+
+impl GoOptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[("--module", |o| o.go.module.is_some())];
+}
+
+const GO_MAIN: &str = r#"package main
+
+import "fmt"
+
+func main() {
+	fmt.Println("Hello, world!")
+}
+"#;
+
+pub(crate) fn go_project_plan(meta: &Metadata, options: &GoOptions) -> Plan {
+    let module = match &options.module {
+        Some(module) => module.clone(),
+        None => meta.name.to_string_lossy().into_owned(),
+    };
+    let cmd = Command::new("go")
+        .arg("mod")
+        .arg("init")
+        .creates("go.mod")
+        .arg(&module);
+
+    let mut plan = Plan::new();
+    plan.create_dir("");
+    plan.run(cmd.current_dir(""));
+    plan.write_file("main.go", GO_MAIN);
+    let readme = Readme {
+        install: vec!["go build".to_string()],
+        usage: vec!["go run .".to_string()],
+        badge: Some(format!(
+            "[![Go Reference](https://pkg.go.dev/badge/{module}.svg)](https://pkg.go.dev/{module})",
+            module = module
+        )),
+    };
+    create_readme(&mut plan, meta, readme);
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::compute_project_name;
+    use crate::test_util::*;
+    use crate::MakeProjectError;
+    use std::fs;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn create_go_project(path: &Path, options: &GoOptions) -> Result<(), MakeProjectError> {
+        go_project_plan(&Metadata::new(compute_project_name(path)), options).execute(path)
+    }
+
+    #[test]
+    fn creating_a_go_project() {
+        if !have_tool("go") {
+            return;
+        }
+        let temp_dir = TempDir::new("mkproject-go-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        create_go_project(&path, &GoOptions::default()).expect("creating Go project");
+
+        assert!(path.join("go.mod").is_file());
+        assert!(path.join("main.go").is_file());
+        assert!(path.join("README.md").is_file());
+
+        let go_mod = fs::read_to_string(path.join("go.mod")).unwrap();
+        assert!(go_mod.starts_with("module myproject\n"));
+
+        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
+        assert_eq!(readme_contents, "# myproject\n");
+    }
+}
@@ -0,0 +1,339 @@
+//! Create new projects for a range of languages, with version control,
+//! licenses and templates set up consistently.
+//!
+//! ```no_run
+//! use mkproject::{Language, Options, ProjectBuilder};
+//!
+//! let mut options = Options::default();
+//! options.rust.lib = true;
+//!
+//! let project = ProjectBuilder::new("/tmp/myproject", Language::Rust)
+//!     .options(options)
+//!     .build()?;
+//! println!("created {}", project.path().display());
+//! # Ok::<(), mkproject::MakeProjectError>(())
+//! ```
+
+use std::ffi::OsStr;
+use std::str::FromStr;
+use std::{env, io};
+
+mod c;
+pub mod config;
+pub mod doctor;
+mod go;
+mod license;
+mod node;
+mod options;
+pub mod plan;
+mod plugin;
+mod project;
+mod python;
+mod python_env;
+mod rust;
+mod template;
+#[cfg(test)]
+mod test_util;
+
+pub use crate::c::{BuildSystem, COptions};
+pub use crate::go::GoOptions;
+pub use crate::license::License;
+pub use crate::node::NodeOptions;
+pub use crate::options::{DependencyOptions, Options, Vcs};
+pub use crate::plan::{Conflict, Progress};
+pub use crate::plugin::{plugins_dir, Plugin};
+pub use crate::project::{default_author, Project, ProjectBuilder};
+pub use crate::python::PythonOptions;
+pub use crate::python_env::PythonEnv;
+pub use crate::rust::{Edition, RustOptions, WorkspaceMember};
+pub use crate::template::{templates_dir, Template};
+
+/// Everything that can go wrong while creating a project. Each kind of error
+/// has its own exit code, see `exit_code`.
+#[derive(Debug)]
+pub enum MakeProjectError {
+    /// Invalid or conflicting options
+    ArgumentError(String),
+    /// An invalid configuration file
+    Config(String),
+    Io(io::Error),
+    /// A command that failed, with the exit code to pass on
+    Process(String, i32),
+}
+
+/// Exit code for a command that ran and failed, whose own exit code is given
+/// in the message
+pub(crate) const COMMAND_FAILED: i32 = 5;
+
+impl MakeProjectError {
+    /// Exit code for the command line tool:
+    ///
+    /// * 2 for invalid options
+    /// * 3 for an invalid configuration file
+    /// * 4 for errors reading or writing files
+    /// * 5 for a command that failed
+    /// * 127 for a program that was not found
+    /// * 128 plus the signal number for a command killed by a signal
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MakeProjectError::ArgumentError(_) => 2,
+            MakeProjectError::Config(_) => 3,
+            MakeProjectError::Io(_) => 4,
+            MakeProjectError::Process(_, code) => *code,
+        }
+    }
+}
+
+impl std::convert::From<io::Error> for MakeProjectError {
+    fn from(e: io::Error) -> MakeProjectError {
+        MakeProjectError::Io(e)
+    }
+}
+
+impl std::fmt::Display for MakeProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            MakeProjectError::ArgumentError(msg) => write!(f, "Error: {}", msg),
+            MakeProjectError::Config(msg) => write!(f, "Error: {}", msg),
+            MakeProjectError::Io(e) => write!(f, "Error: {}", e),
+            MakeProjectError::Process(msg, _) => write!(f, "Error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MakeProjectError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Language {
+    Python,
+    Rust,
+    Go,
+    JavaScript,
+    TypeScript,
+    C,
+    Cpp,
+    /// A language defined by a plugin
+    Plugin(Box<Plugin>),
+}
+
+impl Language {
+    /// Every built in language
+    pub const ALL: [Language; 7] = [
+        Language::Python,
+        Language::Rust,
+        Language::Go,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::C,
+        Language::Cpp,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Language::Python => "python",
+            Language::Rust => "rust",
+            Language::Go => "go",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Plugin(plugin) => plugin.name(),
+        }
+    }
+
+    /// One line summary of the projects created for the language
+    pub fn description(&self) -> &str {
+        match self {
+            Language::Python => "Python package with a virtual environment",
+            Language::Rust => "Rust crate or workspace created with cargo",
+            Language::Go => "Go module",
+            Language::JavaScript => "JavaScript package using npm",
+            Language::TypeScript => "TypeScript package using npm",
+            Language::C => "C program built with CMake or Meson",
+            Language::Cpp => "C++ program built with CMake or Meson",
+            Language::Plugin(plugin) => plugin.description().unwrap_or(""),
+        }
+    }
+
+    /// The language specific flags that can be used with the language
+    pub fn flags(&self) -> Vec<&'static str> {
+        Options::flags(self)
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Language {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Language::parse(s, &env::var_os("PATH").unwrap_or_default())
+    }
+}
+
+impl Language {
+    /// Parse a language, looking for plugin executables in `paths`, a list of
+    /// directories like `PATH`
+    fn parse(s: &str, paths: &OsStr) -> Result<Language, MakeProjectError> {
+        match s {
+            "python" => Ok(Language::Python),
+            "rust" => Ok(Language::Rust),
+            "go" | "golang" => Ok(Language::Go),
+            "javascript" | "js" => Ok(Language::JavaScript),
+            "typescript" | "ts" => Ok(Language::TypeScript),
+            "c" => Ok(Language::C),
+            "cpp" | "c++" => Ok(Language::Cpp),
+            o => match Plugin::find_in(o, paths)? {
+                Some(plugin) => Ok(Language::Plugin(Box::new(plugin))),
+                None => {
+                    let mut msg = format!("parsing model from given command: `{}`", o);
+                    let names = Language::ALL
+                        .iter()
+                        .map(|l| l.as_str().to_string())
+                        .chain(Plugin::names_in(paths));
+                    if let Some(name) = closest(o, names) {
+                        msg.push_str(&format!(", did you mean `{}`?", name));
+                    }
+                    Err(MakeProjectError::ArgumentError(msg))
+                }
+            },
+        }
+    }
+}
+
+/// The candidate most similar to `name`, if it differs in no more than a third
+/// of its characters
+fn closest(name: &str, candidates: impl Iterator<Item = String>) -> Option<String> {
+    candidates
+        .map(|c| (edit_distance(name, &c), c))
+        .filter(|(distance, c)| {
+            *distance <= name.chars().count().max(c.chars().count()).div_ceil(3)
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_python() {
+        let s = "python";
+        assert_eq!(Language::from_str(s).unwrap(), Language::Python);
+    }
+
+    #[test]
+    fn parsing_rust() {
+        let s = "rust";
+        assert_eq!(Language::from_str(s).unwrap(), Language::Rust);
+    }
+
+    #[test]
+    fn parsing_go() {
+        assert_eq!(Language::from_str("go").unwrap(), Language::Go);
+        assert_eq!(Language::from_str("golang").unwrap(), Language::Go);
+    }
+
+    #[test]
+    fn parsing_node_languages() {
+        assert_eq!(Language::from_str("js").unwrap(), Language::JavaScript);
+        assert_eq!(
+            Language::from_str("javascript").unwrap(),
+            Language::JavaScript
+        );
+        assert_eq!(Language::from_str("ts").unwrap(), Language::TypeScript);
+        assert_eq!(
+            Language::from_str("typescript").unwrap(),
+            Language::TypeScript
+        );
+    }
+
+    #[test]
+    fn parsing_c_languages() {
+        assert_eq!(Language::from_str("c").unwrap(), Language::C);
+        assert_eq!(Language::from_str("cpp").unwrap(), Language::Cpp);
+        assert_eq!(Language::from_str("c++").unwrap(), Language::Cpp);
+    }
+
+    #[test]
+    fn exit_codes() {
+        let err = MakeProjectError::ArgumentError("bad".to_string());
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!(err.to_string(), "Error: bad");
+
+        let err = MakeProjectError::Io(io::Error::other("disk full"));
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.to_string(), "Error: disk full");
+
+        let err = MakeProjectError::Process("`pip` failed".to_string(), COMMAND_FAILED);
+        assert_eq!(err.exit_code(), 5);
+    }
+
+    #[test]
+    fn parsing_plugin_languages() {
+        let stubs = test_util::StubTools::new();
+        match Language::parse("stub", &stubs.path()).unwrap() {
+            Language::Plugin(plugin) => assert_eq!(plugin.name(), "stub"),
+            o => panic!("unexpected language: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn parsing_something_else() {
+        let s = "other";
+        assert!(Language::from_str(s).is_err());
+    }
+
+    #[test]
+    fn suggesting_similar_languages() {
+        let message = |s| match Language::from_str(s) {
+            Err(MakeProjectError::ArgumentError(msg)) => msg,
+            o => panic!("unexpected result: {:?}", o),
+        };
+        assert_eq!(
+            message("pyhton"),
+            "parsing model from given command: `pyhton`, did you mean `python`?"
+        );
+        assert!(message("rsut").ends_with("did you mean `rust`?"));
+        assert_eq!(
+            message("cobol"),
+            "parsing model from given command: `cobol`"
+        );
+
+        let stubs = test_util::StubTools::new();
+        match Language::parse("stbu", &stubs.path()) {
+            Err(MakeProjectError::ArgumentError(msg)) => {
+                assert!(msg.ends_with("did you mean `stub`?"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn measuring_edit_distance() {
+        assert_eq!(edit_distance("", "go"), 2);
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
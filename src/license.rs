@@ -0,0 +1,142 @@
+//! License files for new projects. The license texts are embedded in the
+//! binary, so that they can be generated offline.
+
+use crate::template::Variables;
+use crate::MakeProjectError;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum License {
+    Mit,
+    Apache2,
+    Gpl3Only,
+    Gpl3OrLater,
+    Bsd3Clause,
+    Mpl2,
+    Isc,
+}
+
+impl License {
+    /// Every supported license
+    pub const ALL: [License; 7] = [
+        License::Mit,
+        License::Apache2,
+        License::Gpl3Only,
+        License::Gpl3OrLater,
+        License::Bsd3Clause,
+        License::Mpl2,
+        License::Isc,
+    ];
+
+    /// SPDX identifier, as used in package metadata
+    pub fn spdx(self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::Gpl3Only => "GPL-3.0-only",
+            License::Gpl3OrLater => "GPL-3.0-or-later",
+            License::Bsd3Clause => "BSD-3-Clause",
+            License::Mpl2 => "MPL-2.0",
+            License::Isc => "ISC",
+        }
+    }
+
+    /// Contents of the `LICENSE` file, with the year and author filled in.
+    /// Without an author the copyright lines only give the year.
+    pub fn text(self, vars: &Variables) -> String {
+        let text = match self {
+            License::Mit => include_str!("licenses/MIT.txt"),
+            License::Apache2 => include_str!("licenses/Apache-2.0.txt"),
+            // The GPL text has no copyright line of its own
+            License::Gpl3Only | License::Gpl3OrLater => concat!(
+                "Copyright (C) {{year}} {{author}}\n\n",
+                include_str!("licenses/GPL-3.0.txt")
+            ),
+            License::Bsd3Clause => include_str!("licenses/BSD-3-Clause.txt"),
+            License::Mpl2 => include_str!("licenses/MPL-2.0.txt"),
+            License::Isc => include_str!("licenses/ISC.txt"),
+        };
+        if vars.author.is_empty() {
+            // Drop the separator before the missing name, e.g. `2019, `
+            let text = text
+                .replace("{{year}}, {{author}}", "{{year}}")
+                .replace("{{year}} {{author}}", "{{year}}");
+            return vars.render(&text);
+        }
+        vars.render(text)
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.spdx())
+    }
+}
+
+impl FromStr for License {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mit" => Ok(License::Mit),
+            "apache-2.0" | "apache2" => Ok(License::Apache2),
+            // `GPL-3.0` is the deprecated SPDX name for `GPL-3.0-only`
+            "gpl-3.0" | "gpl-3.0-only" | "gpl3" => Ok(License::Gpl3Only),
+            "gpl-3.0-or-later" | "gpl-3.0+" => Ok(License::Gpl3OrLater),
+            "bsd-3-clause" => Ok(License::Bsd3Clause),
+            "mpl-2.0" => Ok(License::Mpl2),
+            "isc" => Ok(License::Isc),
+            _ => Err(MakeProjectError::ArgumentError(format!(
+                "unknown license: `{}`, expected one of MIT, Apache-2.0, GPL-3.0, \
+                 GPL-3.0-or-later, BSD-3-Clause, MPL-2.0 or ISC",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Variables {
+        let mut vars = Variables::new("myproject");
+        vars.author = "Jo Bloggs".to_string();
+        vars.year = "2019".to_string();
+        vars
+    }
+
+    #[test]
+    fn parsing_licenses() {
+        assert_eq!(License::from_str("MIT").unwrap(), License::Mit);
+        assert_eq!(License::from_str("apache-2.0").unwrap(), License::Apache2);
+        assert_eq!(License::from_str("GPL-3.0").unwrap(), License::Gpl3Only);
+        assert!(License::from_str("WTFPL").is_err());
+    }
+
+    #[test]
+    fn filling_in_the_copyright_holder() {
+        let text = License::Mit.text(&vars());
+        assert!(text.starts_with("MIT License\n\nCopyright (c) 2019 Jo Bloggs\n"));
+
+        let text = License::Apache2.text(&vars());
+        assert!(text.contains("Copyright 2019 Jo Bloggs"));
+
+        let text = License::Gpl3OrLater.text(&vars());
+        assert!(text.starts_with("Copyright (C) 2019 Jo Bloggs\n"));
+        assert!(text.contains("GNU GENERAL PUBLIC LICENSE"));
+    }
+
+    #[test]
+    fn leaving_out_a_missing_copyright_holder() {
+        let mut vars = vars();
+        vars.author = String::new();
+        let text = License::Mit.text(&vars);
+        assert!(text.starts_with("MIT License\n\nCopyright (c) 2019\n"));
+        let text = License::Bsd3Clause.text(&vars);
+        assert!(text.contains("Copyright (c) 2019\n"));
+        let text = License::Gpl3OrLater.text(&vars);
+        assert!(text.starts_with("Copyright (C) 2019\n"));
+    }
+}
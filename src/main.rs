@@ -1,217 +1,1258 @@
-use log::debug;
-use std::io::Write;
-use std::path::PathBuf;
+use mkproject::config::{Config, Setting, Source};
+use mkproject::doctor::{language_tools, vcs_tools, Tool};
+use mkproject::{
+    default_author, templates_dir, Conflict, Language, MakeProjectError, Options, Plugin, Progress,
+    ProjectBuilder, Template,
+};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{fs, io, process};
+use std::{env, process};
+
+mod wizard;
+
+use crate::wizard::Wizard;
 use structopt::StructOpt;
 
-#[derive(Debug)]
-pub enum MakeProjectError {
-    ArgumentError(String),
-    Io(io::Error),
-    Process(String, i32),
-}
+const EXIT_STATUS: &str = "EXIT STATUS:
+    0      the project was created
+    2      invalid options
+    3      invalid configuration file
+    4      error reading or writing files
+    5      a command failed, its exit code is given in the message
+    127    a required program was not found
+    128+N  a command was killed by signal N";
 
-impl std::convert::From<io::Error> for MakeProjectError {
-    fn from(e: io::Error) -> MakeProjectError {
-        MakeProjectError::Io(e)
-    }
-}
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "mkproject",
+    about = "Create projects with templates easily",
+    raw(after_help = "EXIT_STATUS")
+)]
+struct Opt {
+    /// Language of the project: python, rust, go, javascript, typescript, c,
+    /// cpp or the name of a plugin
+    #[structopt(short = "l", long = "language", raw(global = "true"))]
+    language: Option<Language>,
 
-impl std::fmt::Display for MakeProjectError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        match self {
-            MakeProjectError::ArgumentError(msg) => write!(f, "Error: {}", msg),
-            MakeProjectError::Io(e) => e.fmt(f),
-            MakeProjectError::Process(msg, _) => write!(f, "Error: {}", msg),
-        }
-    }
-}
+    #[structopt(flatten)]
+    options: Options,
+
+    /// Configuration file to use instead of ~/.config/mkproject/config.toml
+    #[structopt(long = "config", parse(from_os_str), raw(global = "true"))]
+    config: Option<PathBuf>,
+
+    /// Print what would be created and run, without touching the filesystem
+    #[structopt(long = "dry-run", raw(global = "true"))]
+    dry_run: bool,
+
+    /// Never prompt, even when the language or path is missing
+    #[structopt(long = "no-input", raw(global = "true"))]
+    no_input: bool,
+
+    /// Show the output of every command as it runs
+    #[structopt(short = "v", long = "verbose", raw(global = "true"))]
+    verbose: bool,
 
-impl std::error::Error for MakeProjectError {}
+    #[structopt(parse(from_os_str))]
+    path: Option<PathBuf>,
 
-#[derive(Debug, PartialEq, Eq)]
-enum Language {
-    Python,
-    Rust,
+    #[structopt(subcommand)]
+    command: Option<Subcommand>,
 }
 
-impl FromStr for Language {
-    type Err = MakeProjectError;
+#[derive(Debug, StructOpt)]
+enum Subcommand {
+    /// Inspect the configuration
+    #[structopt(name = "config")]
+    Config(ConfigCommand),
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "python" => Ok(Language::Python),
-            "rust" => Ok(Language::Rust),
-            o => Err(MakeProjectError::ArgumentError(format!(
-                "parsing model from given command: `{}`",
-                o
-            ))),
-        }
-    }
+    /// Create a project inside an existing directory, the current one by
+    /// default
+    #[structopt(name = "init")]
+    Init(InitCommand),
+
+    /// List the languages, plugins and templates that can be used
+    #[structopt(name = "list")]
+    List,
+
+    /// Check that the programs each language needs are installed, or only
+    /// those for `--language`
+    #[structopt(name = "doctor")]
+    Doctor,
 }
 
 #[derive(Debug, StructOpt)]
-#[structopt(name = "mkproject", about = "Create projects with templates easily")]
-struct Opt {
-    #[structopt(short = "l", long = "language")]
-    language: Language,
+struct InitCommand {
+    /// What to do with files that already exist: skip, overwrite or abort.
+    /// Asks about each file when run interactively, otherwise aborts. Files
+    /// created by commands such as `cargo init` cannot be skipped.
+    #[structopt(long = "on-conflict")]
+    on_conflict: Option<Conflict>,
 
     #[structopt(parse(from_os_str))]
-    path: PathBuf,
+    path: Option<PathBuf>,
 }
 
-fn run_command(cmd: &mut process::Command) -> Result<(), MakeProjectError> {
-    let op = cmd.output()?;
-    check_status(op)
+#[derive(Debug, StructOpt)]
+enum ConfigCommand {
+    /// Print the effective configuration and where each value came from
+    #[structopt(name = "show")]
+    Show,
 }
 
-fn check_status(op: process::Output) -> Result<(), MakeProjectError> {
-    let status = op.status;
-    if !status.success() {
-        let code = status.code().expect("process should have an exit code");
+impl Opt {
+    fn language(&self) -> Result<Language, MakeProjectError> {
+        self.language.clone().ok_or_else(|| {
+            MakeProjectError::ArgumentError(
+                "no language given, pass `--language` or set `language` in the config file"
+                    .to_string(),
+            )
+        })
+    }
 
-        return Err(MakeProjectError::Process(
-            format!("running `cargo new` command, exit code: {}", code),
-            code,
-        ));
+    fn path(&self) -> Result<&Path, MakeProjectError> {
+        match (&self.command, &self.path) {
+            (Some(Subcommand::Init(_)), Some(_)) => Err(MakeProjectError::ArgumentError(
+                "the path to initialise goes after `init`".to_string(),
+            )),
+            (Some(Subcommand::Init(init)), None) => {
+                Ok(init.path.as_deref().unwrap_or_else(|| Path::new(".")))
+            }
+            (_, path) => path.as_deref().ok_or_else(|| {
+                MakeProjectError::ArgumentError("no project path given".to_string())
+            }),
+        }
+    }
+
+    fn init(&self) -> Option<&InitCommand> {
+        match &self.command {
+            Some(Subcommand::Init(init)) => Some(init),
+            _ => None,
+        }
     }
-    Ok(())
-}
 
-fn create_readme(path: &PathBuf) -> Result<(), MakeProjectError> {
-    debug!("Creating initial readme");
-    let readme_path = path.join("README.md");
-    let project_name = compute_project_name(path);
-    let mut file = fs::File::create(readme_path)?;
+    /// Whether to prompt for the language or path, which are missing
+    fn wants_prompts(&self, config: &Config) -> bool {
+        let no_language = self.language.is_none() && config.language.is_none();
+        self.command.is_none() && (self.path.is_none() || no_language)
+    }
 
-    let project_name = project_name
-        .into_string()
-        .expect("path contains invalid UTF-8 data");
-    writeln!(file, "# {}", project_name)?;
-    Ok(())
-}
+    /// Reject language specific options that do not apply to the chosen language
+    fn validate(&self) -> Result<(), MakeProjectError> {
+        self.options.validate(self.language.as_ref())
+    }
+
+    /// The builder for the project described by the command line
+    fn builder(&self) -> Result<ProjectBuilder, MakeProjectError> {
+        let options = self.options.clone();
+        let progress = if self.verbose {
+            Progress::Verbose
+        } else {
+            Progress::Steps
+        };
+        let mut builder = ProjectBuilder::new(self.path()?, self.language()?)
+            .options(options)
+            .progress(progress);
+        if let Some(init) = self.init() {
+            builder = builder.init();
+            if let Some(conflict) = init.on_conflict {
+                builder = builder.on_conflict(conflict);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Fill in anything not given on the command line from the config file,
+    /// returning every effective setting and where it came from
+    fn merge_config(&mut self, config: &Config) -> Result<Vec<Setting>, MakeProjectError> {
+        let mut settings = Vec::new();
+
+        let language = parse_config_value(config, "language", &config.language)?;
+        merge(
+            &mut settings,
+            "language",
+            &mut self.language,
+            language,
+            config,
+        );
+
+        // Options from the config file are only defaults, so only those given
+        // on the command line are checked against the language
+        self.validate()?;
+
+        merge(
+            &mut settings,
+            "author",
+            &mut self.options.author,
+            config.author.clone(),
+            config,
+        );
+        if self.options.author.is_none() {
+            self.options.author = default_author();
+            record(
+                &mut settings,
+                "author",
+                &self.options.author,
+                Source::Default,
+            );
+        }
+        merge(
+            &mut settings,
+            "email",
+            &mut self.options.email,
+            config.email.clone(),
+            config,
+        );
+        let vcs = parse_config_value(config, "vcs", &config.vcs)?;
+        merge(&mut settings, "vcs", &mut self.options.vcs, vcs, config);
+        if self.options.commit {
+            record(&mut settings, "commit", &Some(true), Source::CommandLine);
+        } else if let Some(commit) = config.commit {
+            self.options.commit = commit;
+            record(
+                &mut settings,
+                "commit",
+                &Some(commit),
+                config_source(config),
+            );
+        }
+        let commit_message = config.commit_message.clone();
+        merge(
+            &mut settings,
+            "commit_message",
+            &mut self.options.commit_message,
+            commit_message,
+            config,
+        );
+        if self.options.badges {
+            record(&mut settings, "badges", &Some(true), Source::CommandLine);
+        } else if let Some(badges) = config.badges {
+            self.options.badges = badges;
+            record(
+                &mut settings,
+                "badges",
+                &Some(badges),
+                config_source(config),
+            );
+        }
+        let license = parse_config_value(config, "license", &config.license)?;
+        merge(
+            &mut settings,
+            "license",
+            &mut self.options.license,
+            license,
+            config,
+        );
+        merge_list(
+            &mut settings,
+            "pre_create",
+            &mut self.options.pre_create,
+            &config.pre_create,
+            config,
+        );
+        merge_list(
+            &mut settings,
+            "post_create",
+            &mut self.options.post_create,
+            &config.post_create,
+            config,
+        );
+
+        // Language sections are checked whatever the language, but only used
+        // by their own language
+        let edition = parse_config_value(config, "rust.edition", &config.rust.edition)?;
+        let env = parse_config_value(config, "python.env", &config.python.env)?;
+        let build_system = parse_config_value(config, "c.build_system", &config.c.build_system)?;
+
+        if self.language == Some(Language::Rust) {
+            if self.options.rust.lib {
+                record(&mut settings, "rust.lib", &Some(true), Source::CommandLine);
+            } else if let Some(lib) = config.rust.lib {
+                self.options.rust.lib = lib;
+                record(&mut settings, "rust.lib", &Some(lib), config_source(config));
+            }
+            merge(
+                &mut settings,
+                "rust.edition",
+                &mut self.options.rust.edition,
+                edition,
+                config,
+            );
+        }
+
+        if self.language == Some(Language::Python) {
+            let python = config.python.python.clone();
+            merge(
+                &mut settings,
+                "python.python",
+                &mut self.options.python.python,
+                python,
+                config,
+            );
+            if self.options.python.python.is_none() {
+                let default = Some(self.options.python.interpreter());
+                record(&mut settings, "python.python", &default, Source::Default);
+            }
+            merge(
+                &mut settings,
+                "python.env",
+                &mut self.options.python.env,
+                env,
+                config,
+            );
+        }
 
-fn compute_project_name(project_path: &PathBuf) -> std::ffi::OsString {
-    let path = project_path.as_path();
-    let stub = path.file_name().expect("no final path component given");
+        let (deps, dev_deps) = match self.language {
+            Some(Language::Python) => (
+                ("python.dependencies", &config.python.dependencies),
+                ("python.dev_dependencies", &config.python.dev_dependencies),
+            ),
+            Some(Language::Rust) => (
+                ("rust.dependencies", &config.rust.dependencies),
+                ("rust.dev_dependencies", &config.rust.dev_dependencies),
+            ),
+            _ => (("dependencies", &None), ("dev_dependencies", &None)),
+        };
+        merge_list(
+            &mut settings,
+            deps.0,
+            &mut self.options.deps.dependencies,
+            deps.1,
+            config,
+        );
+        let dev_deps_given = merge_list(
+            &mut settings,
+            dev_deps.0,
+            &mut self.options.deps.dev_dependencies,
+            dev_deps.1,
+            config,
+        );
+        // Python projects get IPython unless told otherwise
+        if self.language == Some(Language::Python) && !dev_deps_given {
+            self.options.deps.dev_dependencies = vec!["ipython".to_string()];
+            settings.push(Setting {
+                key: dev_deps.0,
+                value: "ipython".to_string(),
+                source: Source::Default,
+            });
+        }
+
+        if let Some(Language::C) | Some(Language::Cpp) = self.language {
+            merge(
+                &mut settings,
+                "c.build_system",
+                &mut self.options.c.build_system,
+                build_system,
+                config,
+            );
+        }
 
-    stub.to_os_string()
+        if let Some(Language::JavaScript) | Some(Language::TypeScript) = self.language {
+            if self.options.node.no_install {
+                record(
+                    &mut settings,
+                    "node.install",
+                    &Some(false),
+                    Source::CommandLine,
+                );
+            } else if let Some(install) = config.node.install {
+                self.options.node.no_install = !install;
+                record(
+                    &mut settings,
+                    "node.install",
+                    &Some(install),
+                    config_source(config),
+                );
+            }
+        }
+
+        Ok(settings)
+    }
 }
 
-fn create_python_project(path: &PathBuf) -> Result<(), MakeProjectError> {
-    debug!("Creating dir: {:?}", path);
+fn config_source(config: &Config) -> Source {
+    Source::ConfigFile(config.path.clone().unwrap_or_default())
+}
 
-    fs::create_dir(&path)?;
+fn record<T: ToString>(
+    settings: &mut Vec<Setting>,
+    key: &'static str,
+    value: &Option<T>,
+    source: Source,
+) {
+    if let Some(value) = value {
+        settings.push(Setting {
+            key,
+            value: value.to_string(),
+            source,
+        });
+    }
+}
 
-    let venv_path = path.join("venv");
+/// Use the value from the config file if none was given on the command line
+fn merge<T: ToString>(
+    settings: &mut Vec<Setting>,
+    key: &'static str,
+    value: &mut Option<T>,
+    from_config: Option<T>,
+    config: &Config,
+) {
+    if value.is_some() {
+        record(settings, key, value, Source::CommandLine);
+    } else if from_config.is_some() {
+        *value = from_config;
+        record(settings, key, value, config_source(config));
+    }
+}
 
-    debug!("Creating virtual environment");
-    run_command(
-        process::Command::new("python3")
-            .arg("-m")
-            .arg("venv")
-            .arg(&venv_path),
-    )?;
+/// Use the list from the config file if none was given on the command line,
+/// returning whether the list was given in either place
+fn merge_list(
+    settings: &mut Vec<Setting>,
+    key: &'static str,
+    value: &mut Vec<String>,
+    from_config: &Option<Vec<String>>,
+    config: &Config,
+) -> bool {
+    let source = if !value.is_empty() {
+        Source::CommandLine
+    } else if let Some(from_config) = from_config {
+        value.clone_from(from_config);
+        config_source(config)
+    } else {
+        return false;
+    };
+    settings.push(Setting {
+        key,
+        value: value.join(", "),
+        source,
+    });
+    true
+}
 
-    debug!("Installing ipython");
-    run_command(
-        process::Command::new(venv_path.join("bin").join("pip"))
-            .arg("install")
-            .arg("ipython"),
-    )?;
+fn parse_config_value<T: FromStr<Err = MakeProjectError>>(
+    config: &Config,
+    key: &str,
+    value: &Option<String>,
+) -> Result<Option<T>, MakeProjectError> {
+    match value {
+        Some(value) => value.parse().map(Some).map_err(|e| {
+            let msg = match e {
+                MakeProjectError::ArgumentError(msg) => msg,
+                o => o.to_string(),
+            };
+            MakeProjectError::Config(format!("{}: `{}`: {}", config_source(config), key, msg))
+        }),
+        None => Ok(None),
+    }
+}
 
-    create_readme(path)?;
-    Ok(())
+fn show_config(config: &Config, settings: &[Setting]) {
+    match &config.path {
+        Some(path) => println!("# configuration file: {}", path.display()),
+        None => println!("# no configuration file found"),
+    }
+    for setting in settings {
+        println!("{}", setting);
+    }
 }
 
-// TODO: add optional language-specific arguments
-fn create_rust_project(path: &PathBuf) -> Result<(), MakeProjectError> {
-    debug!("Running cargo new");
-    run_command(
-        process::Command::new("cargo")
-            .arg("new")
-            .arg(path.to_str().unwrap()),
-    )?;
+/// Write every language, plugin and template, with a description and the
+/// language specific options of each
+fn list(out: &mut impl Write) -> Result<(), MakeProjectError> {
+    writeln!(out, "Languages:")?;
+    for language in Language::ALL.iter() {
+        writeln!(out, "  {:<12}{}", language.as_str(), language.description())?;
+        let flags = language.flags();
+        if !flags.is_empty() {
+            writeln!(out, "  {:<12}options: {}", "", flags.join(", "))?;
+        }
+    }
+
+    writeln!(out, "\nPlugins:")?;
+    let plugins = Plugin::names()
+        .iter()
+        .filter_map(|name| Plugin::find(name).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    if plugins.is_empty() {
+        writeln!(out, "  none")?;
+    }
+    for plugin in plugins {
+        let description = plugin.description().unwrap_or("");
+        writeln!(out, "  {:<12}{}", plugin.name(), description)?;
+        writeln!(out, "  {:<12}from {}", "", plugin.path().display())?;
+    }
 
-    create_readme(path)?;
+    writeln!(out, "\nTemplates:")?;
+    let templates = match templates_dir() {
+        Some(dir) => Template::all(&dir)?,
+        None => Vec::new(),
+    };
+    if templates.is_empty() {
+        writeln!(out, "  none")?;
+    }
+    for template in templates {
+        let description = template.description().unwrap_or("");
+        writeln!(out, "  {:<12}{}", template.name(), description)?;
+    }
     Ok(())
 }
 
-fn main() -> Result<(), MakeProjectError> {
-    env_logger::init();
+/// Write the result of checking the tools of `language`, or of every language
+/// and plugin, failing if any of them has a problem
+fn doctor(
+    out: &mut impl Write,
+    language: Option<&Language>,
+    options: &Options,
+) -> Result<(), MakeProjectError> {
+    let languages = match language {
+        Some(language) => vec![language.clone()],
+        None => {
+            let mut languages = Language::ALL.to_vec();
+            for name in Plugin::names() {
+                languages.extend(Plugin::find(&name)?.map(|p| Language::Plugin(Box::new(p))));
+            }
+            languages
+        }
+    };
+    let mut sections: Vec<_> = languages
+        .iter()
+        .map(|l| (l.as_str().to_string(), language_tools(l, options)))
+        .collect();
+    sections.push(("version control".to_string(), vcs_tools(options)));
 
-    let opts = Opt::from_args();
+    let mut problems = 0;
+    for (name, tools) in sections {
+        writeln!(out, "{}:", name)?;
+        if tools.is_empty() {
+            writeln!(out, "  ok       nothing to check")?;
+        }
+        for check in tools.iter().map(Tool::check) {
+            let status = if check.is_ok() { "ok" } else { "problem" };
+            writeln!(out, "  {:<9}{}", status, check)?;
+            if !check.is_ok() {
+                problems += 1;
+            }
+        }
+    }
+
+    match problems {
+        0 => Ok(()),
+        1 => Err(MakeProjectError::Process(
+            "1 problem found".to_string(),
+            127,
+        )),
+        n => Err(MakeProjectError::Process(
+            format!("{} problems found", n),
+            127,
+        )),
+    }
+}
 
-    let result = match opts.language {
-        Language::Python => create_python_project(&opts.path),
-        Language::Rust => create_rust_project(&opts.path),
+fn run() -> Result<(), MakeProjectError> {
+    let mut opts = match Opt::from_iter_safe(env::args_os()) {
+        Ok(opts) => opts,
+        // Usage errors exit like any other invalid option, help and version
+        // output exits successfully
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            process::exit(2);
+        }
+        Err(e) => e.exit(),
     };
+    if let Some(Subcommand::List) = opts.command {
+        return list(&mut io::stdout().lock());
+    }
+    let config = Config::load_or_default(opts.config.as_deref())?;
+
+    // Ask for whatever is missing when run by a person
+    let can_prompt = !opts.no_input && io::stdin().is_terminal();
+    let interactive = can_prompt && opts.wants_prompts(&config);
+    let mut wizard = Wizard::new(io::stdin().lock(), io::stdout());
+    if interactive {
+        wizard.ask(&mut opts, &config)?;
+    }
+    // The doctor checks every language unless one is given on the command line
+    let given_language = opts.language.clone();
+    let settings = opts.merge_config(&config)?;
 
-    match result {
-        Err(MakeProjectError::Process(msg, code)) => {
-            eprintln!("Error: {}", msg);
-            process::exit(code);
+    match opts.command {
+        Some(Subcommand::Config(ConfigCommand::Show)) => {
+            show_config(&config, &settings);
+            return Ok(());
+        }
+        Some(Subcommand::Doctor) => {
+            return doctor(
+                &mut io::stdout().lock(),
+                given_language.as_ref(),
+                &opts.options,
+            );
         }
         _ => {}
     }
 
-    Ok(())
+    if interactive && !opts.dry_run && !wizard.confirm_summary(&opts)? {
+        println!("Nothing was created");
+        return Ok(());
+    }
+
+    let path = opts.path()?;
+    let builder = opts.builder()?;
+    let mut plan = builder.plan()?;
+    if opts.dry_run {
+        plan.check_target(path)?;
+        plan.check_conflicts(path)?;
+        for line in plan.describe(path) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    // Previewing works without the tools, creating the project does not
+    builder.check_tools()?;
+    if can_prompt {
+        for conflict in plan.conflicts(path) {
+            let resolution = wizard.resolve_conflict(&conflict)?;
+            plan.resolve(&conflict, resolution)?;
+        }
+    }
+
+    for note in plan.notes() {
+        println!("{}", note);
+    }
+    plan.execute(path)
+}
+
+fn main() {
+    env_logger::init();
+
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(e.exit_code());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mkproject::plan::Plan;
+    use mkproject::{Edition, PythonEnv};
+    use std::fs;
     use tempdir::TempDir;
 
+    fn project_plan(opts: &Opt) -> Result<Plan, MakeProjectError> {
+        opts.builder()?.plan()
+    }
+
+    #[test]
+    fn rust_options_with_python_are_rejected() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "python", "--lib", "myproject"]).unwrap();
+        match opts.validate() {
+            Err(MakeProjectError::ArgumentError(msg)) => assert!(msg.contains("--lib")),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn python_options_with_rust_are_rejected() {
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "rust",
+            "--python",
+            "python3.7",
+            "myproject",
+        ])
+        .unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn matching_options_are_accepted() {
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "rust",
+            "--lib",
+            "--edition",
+            "2018",
+            "myproject",
+        ])
+        .unwrap();
+        assert!(opts.validate().is_ok());
+        assert!(opts.options.rust.lib);
+        assert_eq!(opts.options.rust.edition, Some(Edition::E2018));
+    }
+
+    fn config(text: &str) -> Config {
+        let mut config: Config = toml::from_str(text).unwrap();
+        config.path = Some(PathBuf::from("/home/me/.config/mkproject/config.toml"));
+        config
+    }
+
+    #[test]
+    fn config_values_fill_in_missing_options() {
+        let config = config(
+            "language = \"rust\"\nauthor = \"Jo Bloggs\"\n[rust]\nedition = \"2018\"\nlib = true\n",
+        );
+        let mut opts = Opt::from_iter_safe(&["mkproject", "myproject"]).unwrap();
+        let settings = opts.merge_config(&config).unwrap();
+
+        assert_eq!(opts.language, Some(Language::Rust));
+        assert_eq!(opts.options.author.as_deref(), Some("Jo Bloggs"));
+        assert_eq!(opts.options.rust.edition, Some(Edition::E2018));
+        assert!(opts.options.rust.lib);
+
+        let source = Source::ConfigFile(config.path.clone().unwrap());
+        assert!(settings.contains(&Setting {
+            key: "language",
+            value: "rust".to_string(),
+            source: source.clone(),
+        }));
+        assert!(settings.contains(&Setting {
+            key: "rust.edition",
+            value: "2018".to_string(),
+            source,
+        }));
+    }
+
+    #[test]
+    fn hooks_from_config() {
+        let config = config("post_create = [\"pre-commit install\", \"code .\"]\n");
+        let mut opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "c", "--pre-create", "true", "x"]).unwrap();
+        let settings = opts.merge_config(&config).unwrap();
+
+        assert_eq!(opts.options.pre_create, ["true"]);
+        assert_eq!(opts.options.post_create, ["pre-commit install", "code ."]);
+        assert!(settings.contains(&Setting {
+            key: "post_create",
+            value: "pre-commit install, code .".to_string(),
+            source: Source::ConfigFile(config.path.clone().unwrap()),
+        }));
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let config = config("language = \"rust\"\nauthor = \"Jo Bloggs\"\n");
+        let mut opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "python",
+            "--author",
+            "Someone",
+            "myproject",
+        ])
+        .unwrap();
+        let settings = opts.merge_config(&config).unwrap();
+
+        assert_eq!(opts.language, Some(Language::Python));
+        assert_eq!(opts.options.author.as_deref(), Some("Someone"));
+        assert!(settings.contains(&Setting {
+            key: "author",
+            value: "Someone".to_string(),
+            source: Source::CommandLine,
+        }));
+        assert!(settings.contains(&Setting {
+            key: "python.python",
+            value: "python3".to_string(),
+            source: Source::Default,
+        }));
+    }
+
+    #[test]
+    fn config_options_for_other_languages_are_ignored() {
+        let rust = config("language = \"python\"\n[rust]\nedition = \"2018\"\n");
+        let mut opts = Opt::from_iter_safe(&["mkproject", "myproject"]).unwrap();
+        opts.merge_config(&rust).unwrap();
+        assert_eq!(opts.options.rust.edition, None);
+        assert!(project_plan(&opts).is_ok());
+
+        let python = config("[python]\npython = \"python3\"\n[c]\nbuild_system = \"meson\"\n");
+        let mut opts = Opt::from_iter_safe(&["mkproject", "-l", "rust", "myproject"]).unwrap();
+        opts.merge_config(&python).unwrap();
+        assert_eq!(opts.options.python.python, None);
+        assert!(project_plan(&opts).is_ok());
+
+        // ...but the same option on the command line is still an error
+        let mut opts =
+            Opt::from_iter_safe(&["mkproject", "--edition", "2018", "myproject"]).unwrap();
+        assert!(opts.merge_config(&rust).is_err());
+    }
+
+    #[test]
+    fn invalid_config_values_are_reported() {
+        let config = config("language = \"cobol\"\n");
+        let mut opts = Opt::from_iter_safe(&["mkproject", "myproject"]).unwrap();
+        match opts.merge_config(&config) {
+            Err(MakeProjectError::Config(msg)) => {
+                assert!(msg.contains("config.toml"));
+                assert!(msg.contains("cobol"));
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
     #[test]
-    fn parsing_python() {
-        let s = "python";
-        assert_eq!(Language::from_str(s).unwrap(), Language::Python);
+    fn parsing_init() {
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "init",
+            "-l",
+            "rust",
+            "--lib",
+            "--on-conflict",
+            "skip",
+            "existing",
+        ])
+        .unwrap();
+        assert_eq!(opts.language, Some(Language::Rust));
+        assert!(opts.options.rust.lib);
+        assert_eq!(opts.path().unwrap(), Path::new("existing"));
+        assert_eq!(opts.init().unwrap().on_conflict, Some(Conflict::Skip));
+
+        let opts = Opt::from_iter_safe(&["mkproject", "-l", "go", "init"]).unwrap();
+        assert_eq!(opts.path().unwrap(), Path::new("."));
+    }
+
+    #[test]
+    fn listing_languages() {
+        let opts = Opt::from_iter_safe(&["mkproject", "list"]).unwrap();
+        match opts.command {
+            Some(Subcommand::List) => {}
+            o => panic!("unexpected command: {:?}", o),
+        }
+
+        let mut output = Vec::new();
+        list(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Languages:\n  python      Python package"));
+        assert!(output.contains(
+            "  rust        Rust crate or workspace created with cargo\n              \
+             options: --lib, --edition, --name, --workspace, --member, --dep, --dev-dep\n"
+        ));
+        assert!(output.contains("\nPlugins:\n"));
+        assert!(output.contains("\nTemplates:\n"));
     }
 
     #[test]
-    fn parsing_rust() {
-        let s = "rust";
-        assert_eq!(Language::from_str(s).unwrap(), Language::Rust);
+    fn checking_tools_with_the_doctor() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "doctor", "-l", "c", "--vcs", "none"]).unwrap();
+        match opts.command {
+            Some(Subcommand::Doctor) => {}
+            o => panic!("unexpected command: {:?}", o),
+        }
+
+        let mut output = Vec::new();
+        doctor(&mut output, opts.language.as_ref(), &opts.options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "c:\n  ok       nothing to check\nversion control:\n  ok       nothing to check\n"
+        );
+
+        let opts = Opt::from_iter_safe(&["mkproject", "doctor", "-l", "rust"]).unwrap();
+        let mut output = Vec::new();
+        doctor(&mut output, opts.language.as_ref(), &opts.options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("rust:\n  ok       `cargo` 1."));
+        assert!(output.contains("version control:\n  ok       `git` "));
+    }
+
+    #[test]
+    fn parsing_config_show() {
+        let opts = Opt::from_iter_safe(&["mkproject", "config", "show"]).unwrap();
+        match opts.command {
+            Some(Subcommand::Config(ConfigCommand::Show)) => {}
+            o => panic!("unexpected command: {:?}", o),
+        }
+        assert_eq!(opts.path, None);
     }
 
     #[test]
-    fn parsing_something_else() {
-        let s = "other";
-        assert!(Language::from_str(s).is_err());
+    fn planning_a_python_project() {
+        let mut opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "python", "--dry-run", "/tmp/myproject"])
+                .unwrap();
+        opts.merge_config(&Config::default()).unwrap();
+
+        assert_eq!(
+            project_plan(&opts).unwrap().describe(opts.path().unwrap()),
+            vec![
+                "create directory /tmp/myproject",
+                "write file /tmp/myproject/pyproject.toml",
+                "create directory /tmp/myproject/src/myproject",
+                "write file /tmp/myproject/src/myproject/__init__.py",
+                "create directory /tmp/myproject/tests",
+                "write file /tmp/myproject/tests/test_myproject.py",
+                "write file /tmp/myproject/README.md",
+                "write file /tmp/myproject/requirements-dev.txt",
+                "write file /tmp/myproject/.gitignore",
+                "run `git init` in /tmp/myproject",
+                "run `python3 -m venv /tmp/myproject/venv`",
+                "run `/tmp/myproject/venv/bin/pip install -r /tmp/myproject/requirements-dev.txt`",
+                "run `/tmp/myproject/venv/bin/pip install -e /tmp/myproject`",
+            ]
+        );
     }
 
     #[test]
-    fn creating_a_rust_project() {
+    fn creating_a_rust_project_with_dependencies() {
         let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
         let path = temp_dir.path().join("myproject");
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "rust",
+            "--vcs",
+            "none",
+            "--dep",
+            "serde@1.0",
+            "--dep",
+            "log@0.4",
+            "--dev-dep",
+            "tempfile@3",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        project_plan(&opts)
+            .unwrap()
+            .execute(&path)
+            .expect("creating Rust project");
+
+        let manifest: toml::Value =
+            toml::from_str(&fs::read_to_string(path.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(manifest["dependencies"]["serde"].as_str(), Some("1.0"));
+        assert_eq!(manifest["dependencies"]["log"].as_str(), Some("0.4"));
+        assert_eq!(manifest["dev-dependencies"]["tempfile"].as_str(), Some("3"));
+    }
+
+    #[test]
+    fn rust_dependencies_need_a_version() {
+        let opts = Opt::from_iter_safe(&["mkproject", "-l", "rust", "--dep", "log", "x"]).unwrap();
+        match project_plan(&opts) {
+            Err(MakeProjectError::ArgumentError(msg)) => assert_eq!(
+                msg,
+                "invalid crate dependency: `log`, expected `name@version`, e.g. `serde@1.0`"
+            ),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn invalid_workspace_options_are_rejected() {
+        for args in &[
+            &["mkproject", "-l", "rust", "--member", "core", "x"][..],
+            &["mkproject", "-l", "rust", "--workspace", "--lib", "x"][..],
+            &["mkproject", "-l", "rust", "--workspace", "--name", "y", "x"][..],
+            &["mkproject", "-l", "go", "--workspace", "x"][..],
+        ] {
+            let opts = Opt::from_iter_safe(*args).unwrap();
+            assert!(opts.validate().is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn creating_and_extending_a_rust_workspace() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myworkspace");
+        let workspace_opts = |path: &Path, members: &[&str]| {
+            let mut args = vec!["mkproject", "-l", "rust", "--workspace", "--vcs", "none"];
+            for member in members {
+                args.push("--member");
+                args.push(member);
+            }
+            args.push(path.to_str().unwrap());
+            Opt::from_iter_safe(&args).unwrap()
+        };
 
-        create_rust_project(&path).expect("creating Rust project");
+        project_plan(&workspace_opts(&path, &["core", "cli:bin"]))
+            .unwrap()
+            .execute(&path)
+            .expect("creating a Rust workspace");
 
-        assert!(path.join("Cargo.toml").is_file());
-        assert!(path.join("src").is_dir());
-        assert!(path.join("src").join("main.rs").is_file());
+        let manifest: toml::Value =
+            toml::from_str(&fs::read_to_string(path.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(
+            manifest["workspace"]["members"],
+            toml::Value::try_from(vec!["crates/core", "crates/cli"]).unwrap()
+        );
+        assert!(path.join("crates/core/src/lib.rs").is_file());
+        assert!(path.join("crates/cli/src/main.rs").is_file());
         assert!(path.join("README.md").is_file());
 
-        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
-        assert_eq!(readme_contents, "# myproject\n");
+        // Adding a member leaves everything else alone
+        let plan = project_plan(&workspace_opts(&path, &["api"])).unwrap();
+        assert_eq!(
+            plan.describe(&path).last().unwrap(),
+            &format!(
+                "add \"crates/api\" to workspace.members in {}",
+                path.join("Cargo.toml").display()
+            )
+        );
+        plan.execute(&path).expect("adding a workspace member");
+
+        let manifest: toml::Value =
+            toml::from_str(&fs::read_to_string(path.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(
+            manifest["workspace"]["members"],
+            toml::Value::try_from(vec!["crates/core", "crates/cli", "crates/api"]).unwrap()
+        );
+        assert!(path.join("crates/api/src/lib.rs").is_file());
+
+        // Hooks run around the new members, other setup is refused
+        let mut opts = workspace_opts(&path, &["web"]);
+        opts.options.post_create = vec!["touch hooked".to_string()];
+        project_plan(&opts).unwrap().execute(&path).unwrap();
+        assert!(path.join("hooked").is_file());
+        let mut opts = workspace_opts(&path, &["db"]);
+        opts.options.template = Some("anything".to_string());
+        match project_plan(&opts) {
+            Err(MakeProjectError::ArgumentError(msg)) => assert_eq!(
+                msg,
+                "`--template` cannot be used when adding members to an existing workspace"
+            ),
+            o => panic!("unexpected result: {:?}", o),
+        }
+
+        // Existing members and non-workspace directories are rejected
+        assert!(project_plan(&workspace_opts(&path, &["core"])).is_err());
+        let package = path.join("crates").join("api");
+        assert!(project_plan(&workspace_opts(&package, &["other"])).is_err());
+    }
+
+    #[test]
+    fn planning_a_go_project_with_a_module_path() {
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "golang",
+            "--module",
+            "example.com/me/myproject",
+            "/tmp/myproject",
+        ])
+        .unwrap();
+        assert!(opts.validate().is_ok());
+
+        assert_eq!(
+            project_plan(&opts).unwrap().describe(opts.path().unwrap()),
+            vec![
+                "create directory /tmp/myproject",
+                "run `go mod init example.com/me/myproject` in /tmp/myproject",
+                "write file /tmp/myproject/main.go",
+                "write file /tmp/myproject/README.md",
+                "write file /tmp/myproject/.gitignore",
+                "run `git init` in /tmp/myproject",
+            ]
+        );
+    }
+
+    #[test]
+    fn go_options_with_rust_are_rejected() {
+        let opts = Opt::from_iter_safe(&["mkproject", "-l", "rust", "--module", "x", "myproject"])
+            .unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn node_options_with_go_are_rejected() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "go", "--no-install", "myproject"]).unwrap();
+        match opts.validate() {
+            Err(MakeProjectError::ArgumentError(msg)) => {
+                assert!(msg.contains("javascript or typescript"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn build_system_with_python_is_rejected() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "python", "--build-system", "meson", "x"])
+                .unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn python_env_with_rust_is_rejected() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "rust", "--python-env", "uv", "x"]).unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn python_env_from_config() {
+        let valid = config("language = \"python\"\n[python]\nenv = \"poetry\"\n");
+        let mut opts = Opt::from_iter_safe(&["mkproject", "myproject"]).unwrap();
+        opts.merge_config(&valid).unwrap();
+        assert_eq!(opts.options.python.env(), PythonEnv::Poetry);
+
+        let invalid = config("[python]\nenv = \"conda-forge\"\n");
+        let mut opts = Opt::from_iter_safe(&["mkproject", "myproject"]).unwrap();
+        assert!(opts.merge_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn creating_a_git_repository_with_an_initial_commit() {
+        let temp_dir = TempDir::new("mkproject-git").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "c",
+            "--commit-message",
+            "Start myproject",
+            "--author",
+            "Jo Bloggs",
+            "--email",
+            "jo@example.com",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+        opts.validate().unwrap();
+
+        project_plan(&opts)
+            .unwrap()
+            .execute(&path)
+            .expect("creating C project");
+
+        let gitignore = fs::read_to_string(path.join(".gitignore")).unwrap();
+        assert_eq!(gitignore, "/build/\n.mkproject.log\n");
+
+        let log = process::Command::new("git")
+            .args(["log", "--format=%an: %s"])
+            .current_dir(&path)
+            .output()
+            .unwrap();
+        assert!(log.status.success());
+        assert_eq!(
+            String::from_utf8(log.stdout).unwrap(),
+            "Jo Bloggs: Start myproject\n"
+        );
+
+        let files = process::Command::new("git")
+            .args(["ls-files"])
+            .current_dir(&path)
+            .output()
+            .unwrap();
+        let files = String::from_utf8(files.stdout).unwrap();
+        assert!(files.contains("CMakeLists.txt"));
+        assert!(files.contains("src/main.c"));
+    }
+
+    #[test]
+    fn rust_projects_get_the_same_git_setup() {
+        let temp_dir = TempDir::new("mkproject-git").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "rust", path.to_str().unwrap()]).unwrap();
+
+        project_plan(&opts)
+            .unwrap()
+            .execute(&path)
+            .expect("creating Rust project");
+
+        assert!(path.join(".git").is_dir());
+        let gitignore = fs::read_to_string(path.join(".gitignore")).unwrap();
+        assert_eq!(gitignore, "/target\n.mkproject.log\n");
+    }
+
+    #[test]
+    fn no_version_control() {
+        let opts =
+            Opt::from_iter_safe(&["mkproject", "-l", "python", "--vcs", "none", "/tmp/p"]).unwrap();
+        let plan = project_plan(&opts).unwrap().describe(opts.path().unwrap());
+        assert!(!plan.iter().any(|line| line.contains("git")));
+
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "python",
+            "--vcs",
+            "none",
+            "--commit",
+            "/tmp/p",
+        ])
+        .unwrap();
+        assert!(opts.validate().is_err());
     }
 
     #[test]
-    fn creating_a_python_project() {
+    fn creating_a_licensed_rust_project() {
         let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
         let path = temp_dir.path().join("myproject");
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "rust",
+            "--license",
+            "apache-2.0",
+            "--author",
+            "Jo Bloggs",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
 
-        create_python_project(&path).expect("creating a Python project");
+        project_plan(&opts)
+            .unwrap()
+            .execute(&path)
+            .expect("creating Rust project");
 
-        assert!(path.join("venv").is_dir());
-        assert!(path.join("README.md").is_file());
+        let license = fs::read_to_string(path.join("LICENSE")).unwrap();
+        assert!(license.contains("Apache License"));
+        assert!(license.contains("Jo Bloggs"));
+
+        let manifest = fs::read_to_string(path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("license = \"Apache-2.0\"\n"));
+    }
+
+    #[test]
+    fn dependencies_from_the_command_line_and_config() {
+        let opts = Opt::from_iter_safe(&[
+            "mkproject",
+            "-l",
+            "python",
+            "--dev-dep",
+            "pytest",
+            "--dev-dep",
+            "mypy",
+            "myproject",
+        ])
+        .unwrap();
+        assert_eq!(opts.options.deps.dev_dependencies, vec!["pytest", "mypy"]);
+        assert_eq!(opts.path, Some(PathBuf::from("myproject")));
 
-        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
-        assert_eq!(readme_contents, "# myproject\n");
+        // Python projects get IPython by default
+        let mut opts = Opt::from_iter_safe(&["mkproject", "-l", "python", "myproject"]).unwrap();
+        opts.merge_config(&Config::default()).unwrap();
+        assert_eq!(opts.options.deps.dev_dependencies, vec!["ipython"]);
 
-        // Check that ipython is installed
-        assert!(path.join("venv").join("bin").join("ipython").is_file());
+        let lists = config(
+            "[python]\ndev_dependencies = []\n\
+             [rust]\ndependencies = [\"anyhow@1\"]\n",
+        );
+        let mut opts = Opt::from_iter_safe(&["mkproject", "-l", "python", "myproject"]).unwrap();
+        opts.merge_config(&lists).unwrap();
+        assert!(opts.options.deps.dev_dependencies.is_empty());
+
+        let mut opts = Opt::from_iter_safe(&["mkproject", "-l", "rust", "myproject"]).unwrap();
+        opts.merge_config(&lists).unwrap();
+        assert_eq!(opts.options.deps.dependencies, vec!["anyhow@1"]);
+    }
+
+    #[test]
+    fn dependencies_with_go_are_rejected() {
+        let opts = Opt::from_iter_safe(&["mkproject", "-l", "go", "--dep", "x", "x"]).unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn invalid_python_project_names_are_rejected() {
+        let opts = Opt::from_iter_safe(&["mkproject", "-l", "python", "/tmp/2fast"]).unwrap();
+        assert!(project_plan(&opts).is_err());
     }
 }
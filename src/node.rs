@@ -0,0 +1,158 @@
+//! JavaScript and TypeScript projects, with a `package.json` for npm.
+
+use crate::options::Flag;
+use crate::plan::{Command, Plan};
+use crate::project::{create_readme, Metadata, Readme};
+use serde_json::json;
+use structopt::StructOpt;
+
+/// Options for JavaScript and TypeScript projects
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct NodeOptions {
+    /// Do not run `npm install`, e.g. when offline (javascript, typescript)
+    #[structopt(long = "no-install", raw(global = "true"))]
+    pub no_install: bool,
+}
+
+impl NodeOptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[("--no-install", |o| o.node.no_install)];
+}
+
+const TSCONFIG: &str = r#"{
+  "compilerOptions": {
+    "target": "ES2020",
+    "module": "commonjs",
+    "rootDir": "src",
+    "outDir": "dist",
+    "strict": true,
+    "esModuleInterop": true,
+    "skipLibCheck": true
+  },
+  "include": ["src"]
+}
+"#;
+
+const NODE_INDEX: &str = "console.log(\"Hello, world!\");\n";
+
+pub(crate) fn node_project_plan(meta: &Metadata, typescript: bool, options: &NodeOptions) -> Plan {
+    // npm package names must be lower case
+    let name = meta.name.to_string_lossy().to_lowercase();
+    let mut package = json!({
+        "name": name,
+        "version": "0.1.0",
+        "private": true,
+    });
+    if let Some(description) = &meta.description {
+        package["description"] = json!(description);
+    }
+    if let Some(license) = meta.license {
+        package["license"] = json!(license.spdx());
+    }
+    if typescript {
+        package["main"] = json!("dist/index.js");
+        package["scripts"] = json!({
+            "build": "tsc",
+            "start": "node dist/index.js",
+        });
+        package["devDependencies"] = json!({
+            "@types/node": "^20.0.0",
+            "typescript": "^5.0.0",
+        });
+    } else {
+        package["main"] = json!("src/index.js");
+        package["scripts"] = json!({
+            "start": "node src/index.js",
+        });
+    }
+    let package = serde_json::to_string_pretty(&package).expect("serialising package.json");
+
+    let mut plan = Plan::new();
+    plan.create_dir("");
+    plan.write_file("package.json", package + "\n");
+    plan.create_dir("src");
+    if typescript {
+        plan.write_file("tsconfig.json", TSCONFIG);
+        plan.write_file("src/index.ts", NODE_INDEX);
+    } else {
+        plan.write_file("src/index.js", NODE_INDEX);
+    }
+    let mut install = vec!["npm install".to_string()];
+    if typescript {
+        install.push("npm run build".to_string());
+    }
+    let readme = Readme {
+        install,
+        usage: vec!["npm start".to_string()],
+        ..Default::default()
+    };
+    create_readme(&mut plan, meta, readme);
+
+    if !options.no_install {
+        plan.run(Command::new("npm").arg("install").current_dir(""));
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::compute_project_name;
+    use crate::test_util::*;
+    use crate::MakeProjectError;
+    use std::fs;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn create_node_project(
+        path: &Path,
+        typescript: bool,
+        options: &NodeOptions,
+    ) -> Result<(), MakeProjectError> {
+        node_project_plan(
+            &Metadata::new(compute_project_name(path)),
+            typescript,
+            options,
+        )
+        .execute(path)
+    }
+
+    #[test]
+    fn creating_a_javascript_project() {
+        if !have_tool("npm") {
+            return;
+        }
+        let temp_dir = TempDir::new("mkproject-node-project").unwrap();
+        let path = temp_dir.path().join("MyProject");
+
+        create_node_project(&path, false, &NodeOptions::default())
+            .expect("creating JavaScript project");
+
+        assert!(path.join("src").join("index.js").is_file());
+        assert!(!path.join("tsconfig.json").exists());
+        assert!(path.join("package-lock.json").is_file());
+        assert!(path.join("README.md").is_file());
+
+        let package: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.join("package.json")).unwrap()).unwrap();
+        assert_eq!(package["name"], "myproject");
+        assert_eq!(package["main"], "src/index.js");
+    }
+
+    #[test]
+    fn creating_a_typescript_project_without_installing() {
+        let temp_dir = TempDir::new("mkproject-node-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        create_node_project(&path, true, &NodeOptions { no_install: true })
+            .expect("creating TypeScript project");
+
+        assert!(path.join("src").join("index.ts").is_file());
+        assert!(path.join("tsconfig.json").is_file());
+        assert!(!path.join("node_modules").exists());
+
+        let package: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.join("package.json")).unwrap()).unwrap();
+        assert_eq!(package["name"], "myproject");
+        assert!(package["devDependencies"]["typescript"].is_string());
+    }
+}
@@ -0,0 +1,212 @@
+//! Options shared by every kind of project, along with the language specific
+//! ones, as given on the command line or through the library API.
+
+use crate::c::COptions;
+use crate::go::GoOptions;
+use crate::license::License;
+use crate::node::NodeOptions;
+use crate::python::PythonOptions;
+use crate::rust::RustOptions;
+use crate::{Language, MakeProjectError};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Vcs {
+    Git,
+    None,
+}
+
+impl Vcs {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Vcs::Git => "git",
+            Vcs::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for Vcs {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Vcs {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "git" => Ok(Vcs::Git),
+            "none" => Ok(Vcs::None),
+            o => Err(MakeProjectError::ArgumentError(format!(
+                "unknown version control system: `{}`",
+                o
+            ))),
+        }
+    }
+}
+
+/// Dependencies added to the generated project
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct DependencyOptions {
+    /// Dependency to add, e.g. `requests>=2` or `serde@1.0`, may be repeated.
+    /// Rust dependencies need a version. (python, rust)
+    #[structopt(long = "dep", number_of_values = 1, raw(global = "true"))]
+    pub dependencies: Vec<String>,
+
+    /// Development dependency to add, e.g. `pytest` or `tempfile@3`, may be
+    /// repeated (python, rust)
+    #[structopt(long = "dev-dep", number_of_values = 1, raw(global = "true"))]
+    pub dev_dependencies: Vec<String>,
+}
+
+impl DependencyOptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[
+        ("--dep", |o| !o.deps.dependencies.is_empty()),
+        ("--dev-dep", |o| !o.deps.dev_dependencies.is_empty()),
+    ];
+}
+
+/// A language specific flag, with whether it has been given in the options
+pub(crate) type Flag = (&'static str, fn(&Options) -> bool);
+
+/// Language specific flags, in the order checked by `Options::validate`, with
+/// the languages they apply to
+const LANGUAGE_FLAGS: [(&[Language], &[Flag]); 6] = [
+    (&[Language::Python], PythonOptions::FLAGS),
+    (&[Language::Rust], RustOptions::FLAGS),
+    (&[Language::Go], GoOptions::FLAGS),
+    (
+        &[Language::JavaScript, Language::TypeScript],
+        NodeOptions::FLAGS,
+    ),
+    (&[Language::C, Language::Cpp], COptions::FLAGS),
+    (
+        &[Language::Python, Language::Rust],
+        DependencyOptions::FLAGS,
+    ),
+];
+
+/// Everything about a new project other than its location and language
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct Options {
+    #[structopt(flatten)]
+    pub rust: RustOptions,
+
+    #[structopt(flatten)]
+    pub python: PythonOptions,
+
+    #[structopt(flatten)]
+    pub go: GoOptions,
+
+    #[structopt(flatten)]
+    pub node: NodeOptions,
+
+    #[structopt(flatten)]
+    pub c: COptions,
+
+    #[structopt(flatten)]
+    pub deps: DependencyOptions,
+
+    /// Name of a template in ~/.config/mkproject/templates to add to the project
+    #[structopt(short = "t", long = "template", raw(global = "true"))]
+    pub template: Option<String>,
+
+    /// One line description of the project, for the README and package
+    /// metadata
+    #[structopt(long = "description", raw(global = "true"))]
+    pub description: Option<String>,
+
+    /// Add badges, e.g. for the license and package registry, to the README
+    #[structopt(long = "badges", raw(global = "true"))]
+    pub badges: bool,
+
+    /// Author name used in templates, defaults to `git config user.name`
+    #[structopt(long = "author", raw(global = "true"))]
+    pub author: Option<String>,
+
+    /// Author email address used in templates
+    #[structopt(long = "email", raw(global = "true"))]
+    pub email: Option<String>,
+
+    /// License of the project: MIT, Apache-2.0, GPL-3.0, GPL-3.0-or-later,
+    /// BSD-3-Clause, MPL-2.0 or ISC
+    #[structopt(long = "license", raw(global = "true"))]
+    pub license: Option<License>,
+
+    /// Version control system to initialise: git or none, defaults to git
+    #[structopt(long = "vcs", raw(global = "true"))]
+    pub vcs: Option<Vcs>,
+
+    /// Make an initial commit containing the generated files
+    #[structopt(long = "commit", raw(global = "true"))]
+    pub commit: bool,
+
+    /// Message for the initial commit, implies `--commit`
+    #[structopt(long = "commit-message", raw(global = "true"))]
+    pub commit_message: Option<String>,
+
+    /// Shell command run before the project is created, from the directory
+    /// that will hold it, may be repeated
+    #[structopt(long = "pre-create", number_of_values = 1, raw(global = "true"))]
+    pub pre_create: Vec<String>,
+
+    /// Shell command run inside the project once it has been created, may be
+    /// repeated
+    #[structopt(long = "post-create", number_of_values = 1, raw(global = "true"))]
+    pub post_create: Vec<String>,
+}
+
+impl Options {
+    pub fn vcs(&self) -> Vcs {
+        self.vcs.unwrap_or(Vcs::Git)
+    }
+
+    /// Message for the initial commit, if one should be made
+    pub fn commit_message(&self) -> Option<&str> {
+        match &self.commit_message {
+            Some(message) => Some(message),
+            None if self.commit => Some("Initial commit"),
+            None => None,
+        }
+    }
+
+    /// The language specific flags that can be used with `language`
+    pub fn flags(language: &Language) -> Vec<&'static str> {
+        LANGUAGE_FLAGS
+            .iter()
+            .filter(|(owners, _)| owners.contains(language))
+            .flat_map(|(_, flags)| flags.iter().map(|(name, _)| *name))
+            .collect()
+    }
+
+    /// Reject language specific options that do not apply to `language`
+    pub fn validate(&self, language: Option<&Language>) -> Result<(), MakeProjectError> {
+        if self.vcs() == Vcs::None && self.commit_message().is_some() {
+            return Err(MakeProjectError::ArgumentError(
+                "an initial commit cannot be made with `--vcs none`".to_string(),
+            ));
+        }
+
+        let language = match language {
+            Some(language) => language,
+            None => return Ok(()),
+        };
+
+        for (owners, flags) in LANGUAGE_FLAGS.iter() {
+            if owners.contains(language) {
+                continue;
+            }
+            if let Some((flag, _)) = flags.iter().find(|(_, given)| given(self)) {
+                let owners: Vec<_> = owners.iter().map(|o| o.as_str()).collect();
+                return Err(MakeProjectError::ArgumentError(format!(
+                    "`{}` can only be used with `--language {}`",
+                    flag,
+                    owners.join(" or ")
+                )));
+            }
+        }
+        self.rust.validate()
+    }
+}
@@ -0,0 +1,1259 @@
+//! Project creation expressed as an ordered list of actions, so that it can
+//! either be executed or printed for a dry run.
+//!
+//! All paths in a plan are relative to the project root. This lets the same
+//! plan be executed inside a staging directory and described using the final
+//! location of the project.
+
+use crate::{MakeProjectError, COMMAND_FAILED};
+use log::debug;
+use std::ffi::{OsStr, OsString};
+use std::io::BufRead;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::{fs, io, process, thread};
+use tempdir::TempDir;
+
+/// Log of everything a plan did, written inside the project
+pub const LOG_FILE: &str = ".mkproject.log";
+
+/// An argument to an external command
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Arg {
+    /// Passed through unchanged
+    Plain(OsString),
+    /// A path relative to the project root
+    Path(PathBuf),
+}
+
+impl Arg {
+    fn resolve(&self, root: &Path) -> OsString {
+        match self {
+            Arg::Plain(s) => s.clone(),
+            Arg::Path(p) => join(root, p).into_os_string(),
+        }
+    }
+}
+
+/// An external command to run as part of creating a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    program: Arg,
+    args: Vec<Arg>,
+    current_dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    /// Shown instead of the command line, e.g. for hooks
+    label: Option<String>,
+    /// Files the command creates, relative to the project root
+    creates: Vec<PathBuf>,
+}
+
+impl Command {
+    /// A program found on `PATH`
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command {
+            program: Arg::Plain(program.as_ref().to_os_string()),
+            args: Vec::new(),
+            current_dir: None,
+            envs: Vec::new(),
+            label: None,
+            creates: Vec::new(),
+        }
+    }
+
+    /// A program inside the project, e.g. `venv/bin/pip`
+    pub fn in_project<P: Into<PathBuf>>(program: P) -> Command {
+        Command {
+            program: Arg::Path(program.into()),
+            args: Vec::new(),
+            current_dir: None,
+            envs: Vec::new(),
+            label: None,
+            creates: Vec::new(),
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Command {
+        self.args.push(Arg::Plain(arg.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn args<I, S>(self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        args.into_iter().fold(self, |cmd, arg| cmd.arg(arg))
+    }
+
+    /// Add a path argument, relative to the project root
+    pub fn path_arg<P: Into<PathBuf>>(mut self, path: P) -> Command {
+        self.args.push(Arg::Path(path.into()));
+        self
+    }
+
+    /// Run the command from a directory relative to the project root
+    pub fn current_dir<P: Into<PathBuf>>(mut self, dir: P) -> Command {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Set an environment variable for the command
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, key: K, value: V) -> Command {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Describe the command by `label` rather than by its arguments
+    pub fn label<S: Into<String>>(mut self, label: S) -> Command {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Note that the command creates a file, which may conflict with one in an
+    /// existing directory
+    pub fn creates<P: Into<PathBuf>>(mut self, path: P) -> Command {
+        self.creates.push(path.into());
+        self
+    }
+
+    fn to_process(&self, root: &Path) -> process::Command {
+        let mut cmd = process::Command::new(self.program.resolve(root));
+        cmd.args(self.args.iter().map(|a| a.resolve(root)));
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(join(root, dir));
+        }
+        cmd.envs(self.envs.iter().map(|(k, v)| (k, v)));
+        cmd
+    }
+
+    /// The label of the command, or the command line quoted in backticks
+    fn name(&self, root: &Path) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => {
+                let mut words = vec![self.program.resolve(root)];
+                words.extend(self.args.iter().map(|a| a.resolve(root)));
+                let words: Vec<_> = words.iter().map(|w| w.to_string_lossy()).collect();
+                format!("`{}`", words.join(" "))
+            }
+        }
+    }
+
+    fn describe(&self, root: &Path) -> String {
+        let command = self.name(root);
+        match &self.current_dir {
+            Some(dir) => format!("{} in {}", command, join(root, dir).display()),
+            None => command,
+        }
+    }
+}
+
+/// A value to store in a TOML file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TomlValue {
+    String(String),
+}
+
+impl TomlValue {
+    fn to_item(&self) -> toml_edit::Item {
+        match self {
+            TomlValue::String(s) => toml_edit::value(s.as_str()),
+        }
+    }
+}
+
+/// A single step of creating a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    CreateDir(PathBuf),
+    WriteFile(PathBuf, Vec<u8>),
+    /// Set a dotted key, e.g. `package.license`, in an existing TOML file
+    SetToml(PathBuf, String, TomlValue),
+    /// Add a string to the array at a dotted key in an existing TOML file,
+    /// unless it is already there
+    AddToTomlArray(PathBuf, String, String),
+    Run(Command),
+}
+
+impl Action {
+    /// Files that the action creates, replaces or changes
+    fn touches(&self) -> Vec<&Path> {
+        match self {
+            Action::CreateDir(_) => Vec::new(),
+            Action::WriteFile(path, _)
+            | Action::SetToml(path, _, _)
+            | Action::AddToTomlArray(path, _, _) => vec![path],
+            Action::Run(cmd) => cmd.creates.iter().map(|p| p.as_path()).collect(),
+        }
+    }
+
+    /// Files that the action creates from scratch
+    fn creates(&self) -> Vec<&Path> {
+        match self {
+            Action::WriteFile(path, _) => vec![path],
+            Action::Run(cmd) => cmd.creates.iter().map(|p| p.as_path()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Carry out the action inside `root`, describing it in the log as if it
+    /// happened at `target`
+    fn execute(&self, root: &Path, target: &Path, log: &mut Log) -> Result<(), MakeProjectError> {
+        log.action(&self.describe(target));
+        match self {
+            Action::CreateDir(path) => {
+                debug!("Creating dir: {:?}", join(root, path));
+                fs::create_dir_all(join(root, path))?;
+            }
+            Action::WriteFile(path, contents) => {
+                debug!("Writing file: {:?}", join(root, path));
+                fs::write(join(root, path), contents)?;
+            }
+            Action::SetToml(path, key, value) => {
+                debug!("Setting {} in {:?}", key, join(root, path));
+                set_toml(&join(root, path), key, value)?;
+            }
+            Action::AddToTomlArray(path, key, value) => {
+                debug!("Adding {:?} to {} in {:?}", value, key, join(root, path));
+                add_to_toml_array(&join(root, path), key, value)?;
+            }
+            Action::Run(cmd) => {
+                debug!("Running {}", cmd.describe(root));
+                run_command(cmd, root, &cmd.describe(target), log)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self, root: &Path) -> String {
+        match self {
+            Action::CreateDir(path) => format!("create directory {}", join(root, path).display()),
+            Action::WriteFile(path, _) => format!("write file {}", join(root, path).display()),
+            Action::SetToml(path, key, _) => {
+                format!("set {} in {}", key, join(root, path).display())
+            }
+            Action::AddToTomlArray(path, key, value) => {
+                format!(
+                    "add {:?} to {} in {}",
+                    value,
+                    key,
+                    join(root, path).display()
+                )
+            }
+            Action::Run(cmd) => format!("run {}", cmd.describe(root)),
+        }
+    }
+}
+
+/// The ordered actions needed to create a project.
+///
+/// Most actions are run inside a staging directory which is moved into place
+/// once they have all succeeded. Actions whose output depends on the final
+/// location of the project (e.g. virtual environments, which cannot be
+/// relocated) are run afterwards, in place.
+///
+/// A plan for an existing project, e.g. one adding a member to a workspace,
+/// runs every action directly in the project instead.
+///
+/// Hooks run before anything is created, from the directory that will hold
+/// the project, and after everything else, inside the finished project.
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// Run before anything is created, from the directory holding the project
+    before: Vec<Action>,
+    staged: Vec<Action>,
+    in_place: Vec<Action>,
+    /// Run once the project is complete, which is kept even if they fail
+    after: Vec<Action>,
+    existing: bool,
+    progress: Progress,
+    /// Existing files that may be replaced
+    overwrite: Vec<PathBuf>,
+    /// Choices made while planning that are worth telling the user about
+    notes: Vec<String>,
+}
+
+/// What to do about a file that a plan for an existing directory would
+/// create, but which is already there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// Leave the existing file alone, without creating or changing it
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Do not create the project
+    Abort,
+}
+
+impl Conflict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Conflict::Skip => "skip",
+            Conflict::Overwrite => "overwrite",
+            Conflict::Abort => "abort",
+        }
+    }
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Conflict {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Conflict::Skip),
+            "overwrite" => Ok(Conflict::Overwrite),
+            "abort" => Ok(Conflict::Abort),
+            o => Err(MakeProjectError::ArgumentError(format!(
+                "unknown conflict resolution: `{}`, expected skip, overwrite or abort",
+                o
+            ))),
+        }
+    }
+}
+
+/// What to show while a plan runs its commands
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// Nothing
+    #[default]
+    Silent,
+    /// A line for each command, e.g. ``Running `cargo new` ... done``
+    Steps,
+    /// Everything the commands write, as they write it
+    Verbose,
+}
+
+impl Plan {
+    pub fn new() -> Plan {
+        Plan::default()
+    }
+
+    /// A plan that changes the existing project at its target
+    pub fn existing() -> Plan {
+        Plan {
+            existing: true,
+            ..Plan::default()
+        }
+    }
+
+    pub fn create_dir<P: Into<PathBuf>>(&mut self, path: P) {
+        self.staged.push(Action::CreateDir(path.into()));
+    }
+
+    pub fn write_file<P: Into<PathBuf>, C: Into<Vec<u8>>>(&mut self, path: P, contents: C) {
+        self.staged
+            .push(Action::WriteFile(path.into(), contents.into()));
+    }
+
+    pub fn set_toml<P: Into<PathBuf>>(&mut self, path: P, key: &str, value: TomlValue) {
+        self.staged
+            .push(Action::SetToml(path.into(), key.to_string(), value));
+    }
+
+    pub fn add_to_toml_array<P: Into<PathBuf>>(&mut self, path: P, key: &str, value: &str) {
+        self.staged.push(Action::AddToTomlArray(
+            path.into(),
+            key.to_string(),
+            value.to_string(),
+        ));
+    }
+
+    /// Run every action directly in the existing project at the target
+    pub(crate) fn set_existing(&mut self) {
+        self.existing = true;
+    }
+
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+    }
+
+    /// Record something worth telling the user before the plan is executed,
+    /// e.g. which interpreter was chosen
+    pub fn note<S: Into<String>>(&mut self, note: S) {
+        self.notes.push(note.into());
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn run(&mut self, cmd: Command) {
+        self.staged.push(Action::Run(cmd));
+    }
+
+    /// Run a command once the project has been moved into its final location
+    pub fn run_in_place(&mut self, cmd: Command) {
+        self.in_place.push(Action::Run(cmd));
+    }
+
+    /// Run a command before anything is created, from the directory that
+    /// will hold the project, or from the project itself if it exists
+    pub fn run_before(&mut self, cmd: Command) {
+        self.before.push(Action::Run(cmd));
+    }
+
+    /// Run a command once the project is complete. The project is kept even
+    /// if the command fails.
+    pub fn run_after(&mut self, cmd: Command) {
+        self.after.push(Action::Run(cmd));
+    }
+
+    /// Set an environment variable for every command added so far, e.g. a
+    /// `PATH` with stand-ins for tools
+    #[cfg(test)]
+    pub(crate) fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) {
+        let actions = self
+            .before
+            .iter_mut()
+            .chain(self.staged.iter_mut())
+            .chain(self.in_place.iter_mut())
+            .chain(self.after.iter_mut());
+        for action in actions {
+            if let Action::Run(cmd) = action {
+                cmd.envs
+                    .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+            }
+        }
+    }
+
+    /// All actions, in the order they are executed
+    pub fn actions(&self) -> impl Iterator<Item = &Action> {
+        self.before
+            .iter()
+            .chain(self.staged.iter())
+            .chain(self.in_place.iter())
+            .chain(self.after.iter())
+    }
+
+    /// Human readable description of every action, as if the project were
+    /// created at `target`
+    pub fn describe(&self, target: &Path) -> Vec<String> {
+        let outside = self.outside(target);
+        self.before
+            .iter()
+            .map(|a| a.describe(&outside))
+            .chain(
+                self.staged
+                    .iter()
+                    .chain(self.in_place.iter())
+                    .chain(self.after.iter())
+                    .map(|a| a.describe(target)),
+            )
+            .collect()
+    }
+
+    /// Where the actions run before the project is created are run from
+    fn outside(&self, target: &Path) -> PathBuf {
+        match target.parent() {
+            _ if self.existing => target.to_path_buf(),
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    /// Check that the plan can be carried out at `target`, without changing
+    /// anything
+    pub fn check_target(&self, target: &Path) -> Result<(), MakeProjectError> {
+        if !self.existing {
+            return check_destination(target);
+        }
+        if !target.is_dir() {
+            return Err(MakeProjectError::ArgumentError(format!(
+                "`{}` is not a directory",
+                target.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Files, relative to `target`, that the plan would create but which
+    /// already exist. Only plans for existing directories can have conflicts.
+    pub fn conflicts(&self, target: &Path) -> Vec<PathBuf> {
+        let mut conflicts: Vec<PathBuf> = Vec::new();
+        if !self.existing {
+            return conflicts;
+        }
+        for path in self.actions().flat_map(|a| a.creates()) {
+            if join(target, path).exists()
+                && !self.overwrite.iter().any(|p| p == path)
+                && !conflicts.iter().any(|p| p == path)
+            {
+                conflicts.push(path.to_path_buf());
+            }
+        }
+        conflicts
+    }
+
+    /// Fail if any conflict has not been resolved
+    pub fn check_conflicts(&self, target: &Path) -> Result<(), MakeProjectError> {
+        let conflicts = self.conflicts(target);
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        let conflicts: Vec<_> = conflicts
+            .iter()
+            .map(|p| format!("`{}`", p.display()))
+            .collect();
+        let verb = if conflicts.len() == 1 {
+            "exists"
+        } else {
+            "exist"
+        };
+        Err(MakeProjectError::ArgumentError(format!(
+            "{} already {} in {}",
+            conflicts.join(", "),
+            verb,
+            target.display()
+        )))
+    }
+
+    /// Deal with a conflict over `path`, as returned by `conflicts`. A file
+    /// created by a command cannot be skipped, as that would mean skipping
+    /// everything else the command does too.
+    pub fn resolve(&mut self, path: &Path, conflict: Conflict) -> Result<(), MakeProjectError> {
+        match conflict {
+            Conflict::Skip => {
+                let creator = self.actions().find_map(|a| match a {
+                    Action::Run(cmd) if cmd.creates.iter().any(|p| p == path) => Some(cmd),
+                    _ => None,
+                });
+                if let Some(cmd) = creator {
+                    return Err(MakeProjectError::ArgumentError(format!(
+                        "`{}` already exists and cannot be skipped, it is created by {} \
+                         along with other files; overwrite it or move it out of the way",
+                        path.display(),
+                        cmd.name(Path::new(".")),
+                    )));
+                }
+                let untouched = |a: &Action| !a.touches().contains(&path);
+                self.staged.retain(untouched);
+                self.in_place.retain(untouched);
+            }
+            Conflict::Overwrite => self.overwrite.push(path.to_path_buf()),
+            Conflict::Abort => {
+                return Err(MakeProjectError::ArgumentError(format!(
+                    "`{}` already exists",
+                    path.display()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the project at `target`, removing everything again if any
+    /// action fails. Changes to an existing project are not undone.
+    ///
+    /// Everything that happened is logged to `LOG_FILE` in the project, or to
+    /// a file in the temporary directory if a command failed.
+    pub fn execute(&self, target: &Path) -> Result<(), MakeProjectError> {
+        let mut log = Log::new(self.progress);
+        match self.execute_logged(target, &mut log) {
+            Ok(()) => log.save(&target.join(LOG_FILE)),
+            Err(MakeProjectError::Process(mut msg, code)) => {
+                let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+                if let Ok(path) = log.save_to_temp_dir(&file_name) {
+                    msg.push_str(&format!("\nThe full log is in {}", path.display()));
+                }
+                Err(MakeProjectError::Process(msg, code))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn execute_logged(&self, target: &Path, log: &mut Log) -> Result<(), MakeProjectError> {
+        self.check_target(target)?;
+        self.check_conflicts(target)?;
+
+        let outside = self.outside(target);
+        for action in &self.before {
+            action.execute(&outside, &outside, log)?;
+        }
+
+        if self.existing {
+            for action in self.staged.iter().chain(self.in_place.iter()) {
+                // Commands may refuse to replace files themselves
+                if let Action::Run(cmd) = action {
+                    for path in cmd.creates.iter().filter(|p| self.overwrite.contains(p)) {
+                        debug!("Removing {:?} to overwrite it", join(target, path));
+                        fs::remove_file(join(target, path))?;
+                    }
+                }
+                action.execute(target, target, log)?;
+            }
+        } else {
+            let staging = Staging::new(target)?;
+            for action in &self.staged {
+                action.execute(staging.path(), target, log)?;
+            }
+
+            let project = staging.commit()?;
+            for action in &self.in_place {
+                action.execute(project.path(), target, log)?;
+            }
+            project.finish();
+        }
+
+        for action in &self.after {
+            action.execute(target, target, log)?;
+        }
+        Ok(())
+    }
+}
+
+/// Paths in a plan are relative to the project root, with the empty path
+/// referring to the root itself
+fn join(root: &Path, path: &Path) -> PathBuf {
+    if path.as_os_str().is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(path)
+    }
+}
+
+fn invalid_toml(path: &Path, msg: String) -> MakeProjectError {
+    MakeProjectError::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), msg),
+    ))
+}
+
+/// Apply `edit` to the item at the dotted `key` in the TOML file at `path`,
+/// creating any missing tables on the way
+fn edit_toml<F>(path: &Path, key: &str, edit: F) -> Result<(), MakeProjectError>
+where
+    F: FnOnce(&mut toml_edit::Item) -> Result<(), MakeProjectError>,
+{
+    let invalid = |msg: String| invalid_toml(path, msg);
+
+    let text = fs::read_to_string(path)?;
+    let mut doc: toml_edit::DocumentMut = text.parse().map_err(|e| invalid(format!("{}", e)))?;
+
+    let parts: Vec<_> = key.split('.').collect();
+    let (last, tables) = parts.split_last().expect("empty TOML key");
+    let mut table = doc.as_table_mut();
+    for name in tables {
+        table = table
+            .entry(name)
+            .or_insert_with(|| {
+                // Only print headers for tables that end up holding values
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                toml_edit::Item::Table(table)
+            })
+            .as_table_mut()
+            .ok_or_else(|| invalid(format!("`{}` is not a table", name)))?;
+    }
+    edit(&mut table[*last])?;
+
+    fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+fn set_toml(path: &Path, key: &str, value: &TomlValue) -> Result<(), MakeProjectError> {
+    edit_toml(path, key, |item| {
+        *item = value.to_item();
+        Ok(())
+    })
+}
+
+fn add_to_toml_array(path: &Path, key: &str, value: &str) -> Result<(), MakeProjectError> {
+    edit_toml(path, key, |item| {
+        if item.is_none() {
+            *item = toml_edit::value(toml_edit::Array::new());
+        }
+        let array = item
+            .as_array_mut()
+            .ok_or_else(|| invalid_toml(path, format!("`{}` is not an array", key)))?;
+        if !array.iter().any(|v| v.as_str() == Some(value)) {
+            array.push(value);
+        }
+        Ok(())
+    })
+}
+
+/// Quote a string for use in a TOML file
+pub(crate) fn toml_str(s: &str) -> String {
+    toml_edit::Value::from(s).to_string()
+}
+
+pub(crate) fn toml_str_array(items: &[String]) -> String {
+    let items: Vec<_> = items.iter().map(|item| toml_str(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn check_destination(target: &Path) -> Result<(), MakeProjectError> {
+    if target.exists() {
+        return Err(MakeProjectError::ArgumentError(format!(
+            "destination `{}` already exists",
+            target.display()
+        )));
+    }
+    Ok(())
+}
+
+/// A project directory that is built in a temporary sibling location and only
+/// renamed into place once every step has succeeded. If it is dropped before
+/// being committed, the staging directory is removed.
+struct Staging {
+    target: PathBuf,
+    path: PathBuf,
+}
+
+impl Staging {
+    fn new(target: &Path) -> Result<Staging, MakeProjectError> {
+        check_destination(target)?;
+
+        let file_name = target.file_name().expect("no final path component given");
+        let mut staging_name = OsString::from(".");
+        staging_name.push(file_name);
+        staging_name.push(".mkproject-staging");
+        let path = target.with_file_name(staging_name);
+
+        // Left behind by a run that was killed before it could clean up
+        if path.exists() {
+            debug!("Removing stale staging dir: {:?}", path);
+            fs::remove_dir_all(&path)?;
+        }
+
+        Ok(Staging {
+            target: target.to_path_buf(),
+            path,
+        })
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the staged project into its final location
+    fn commit(self) -> Result<Committed, MakeProjectError> {
+        debug!("Moving {:?} into place at {:?}", self.path, self.target);
+        fs::rename(&self.path, &self.target)?;
+
+        Ok(Committed {
+            path: self.target.clone(),
+            finished: false,
+        })
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            debug!("Removing staging dir: {:?}", self.path);
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// A project that has been moved into place, but still has in-place actions
+/// to run. The project is removed unless `finish` is called.
+struct Committed {
+    path: PathBuf,
+    finished: bool,
+}
+
+impl Committed {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for Committed {
+    fn drop(&mut self) {
+        if !self.finished && self.path.exists() {
+            debug!("Removing partially created project: {:?}", self.path);
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// Which of a command's output streams a line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Record of the actions and command output of a plan, which also shows the
+/// progress of commands as they run
+struct Log {
+    progress: Progress,
+    contents: String,
+}
+
+impl Log {
+    fn new(progress: Progress) -> Log {
+        Log {
+            progress,
+            contents: String::new(),
+        }
+    }
+
+    fn action(&mut self, description: &str) {
+        self.contents.push_str(description);
+        self.contents.push('\n');
+    }
+
+    fn start(&mut self, cmd: &str) {
+        match self.progress {
+            Progress::Silent => {}
+            Progress::Steps => eprint!("Running {} ... ", cmd),
+            Progress::Verbose => eprintln!("Running {}", cmd),
+        }
+    }
+
+    fn output(&mut self, stream: Stream, line: &str) {
+        if self.progress == Progress::Verbose {
+            match stream {
+                Stream::Stdout => print!("{}", line),
+                Stream::Stderr => eprint!("{}", line),
+            }
+        }
+        self.contents.push_str(line);
+        if !line.ends_with('\n') {
+            self.contents.push('\n');
+        }
+    }
+
+    fn finish(&mut self, success: bool) {
+        if self.progress == Progress::Steps {
+            eprintln!("{}", if success { "done" } else { "failed" });
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), MakeProjectError> {
+        debug!("Writing log: {:?}", path);
+        fs::write(path, &self.contents)?;
+        Ok(())
+    }
+
+    /// Save the log in a new directory of its own in the temporary directory,
+    /// which is kept, so that no one else can choose the file written to
+    fn save_to_temp_dir(&self, name: &str) -> Result<PathBuf, MakeProjectError> {
+        let dir = TempDir::new(&format!("mkproject-{}", name))?.into_path();
+        let path = dir.join(LOG_FILE);
+        self.save(&path)?;
+        Ok(path)
+    }
+}
+
+/// Send each line read from `reader` down `lines`, from another thread
+fn forward<R: io::Read + Send + 'static>(
+    reader: R,
+    stream: Stream,
+    lines: mpsc::Sender<(Stream, String)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = io::BufReader::new(reader);
+        let mut line = Vec::new();
+        while let Ok(n) = reader.read_until(b'\n', &mut line) {
+            if n == 0
+                || lines
+                    .send((stream, String::from_utf8_lossy(&line).into_owned()))
+                    .is_err()
+            {
+                break;
+            }
+            line.clear();
+        }
+    })
+}
+
+/// Run `cmd` inside `root`, passing its output to `log` as it is written.
+/// `description` names the command in messages.
+fn run_command(
+    cmd: &Command,
+    root: &Path,
+    description: &str,
+    log: &mut Log,
+) -> Result<(), MakeProjectError> {
+    log.start(description);
+    let mut child = cmd
+        .to_process(root)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            log.finish(false);
+            match e.kind() {
+                io::ErrorKind::NotFound => MakeProjectError::Process(
+                    format!(
+                        "`{}` was not found, is it installed and on your PATH?",
+                        cmd.program.resolve(root).to_string_lossy()
+                    ),
+                    127,
+                ),
+                _ => e.into(),
+            }
+        })?;
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = child
+        .stdout
+        .take()
+        .map(|out| forward(out, Stream::Stdout, sender.clone()));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|err| forward(err, Stream::Stderr, sender));
+
+    // Kept separately for the error message
+    let mut errors = String::new();
+    for (stream, line) in lines {
+        if stream == Stream::Stderr {
+            errors.push_str(&line);
+        }
+        log.output(stream, &line);
+    }
+    for reader in stdout.into_iter().chain(stderr) {
+        let _ = reader.join();
+    }
+
+    let status = child.wait()?;
+    log.finish(status.success());
+    check_status(description, status, &errors)
+}
+
+/// Turn a failed command into an error naming the command, including what it
+/// wrote to stderr
+fn check_status(
+    cmd: &str,
+    status: process::ExitStatus,
+    stderr: &str,
+) -> Result<(), MakeProjectError> {
+    if status.success() {
+        return Ok(());
+    }
+
+    // Like the shell, report a command killed by a signal as 128 + the signal
+    let (mut msg, code) = match (status.code(), status.signal()) {
+        (Some(code), _) => (
+            format!("{} failed with exit code {}", cmd, code),
+            COMMAND_FAILED,
+        ),
+        (None, Some(signal)) => (
+            format!("{} was killed by signal {}", cmd, signal),
+            128 + signal,
+        ),
+        (None, None) => (format!("{} failed", cmd), COMMAND_FAILED),
+    };
+
+    let stderr = stderr.trim_end();
+    if !stderr.is_empty() {
+        msg.push_str(":\n");
+        msg.push_str(stderr);
+    }
+    Err(MakeProjectError::Process(msg, code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn setting_toml_values() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("Cargo.toml");
+        fs::write(
+            &path,
+            "[package]\nname = \"x\" # the name\n\n[dependencies]\n",
+        )
+        .unwrap();
+
+        set_toml(
+            &path,
+            "package.license",
+            &TomlValue::String("MIT".to_string()),
+        )
+        .unwrap();
+        set_toml(
+            &path,
+            "dependencies.log",
+            &TomlValue::String("0.4".to_string()),
+        )
+        .unwrap();
+        set_toml(
+            &path,
+            "badges.maintenance.status",
+            &TomlValue::String("experimental".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nname = \"x\" # the name\nlicense = \"MIT\"\n\n[dependencies]\nlog = \"0.4\"\n\n\
+             [badges.maintenance]\nstatus = \"experimental\"\n"
+        );
+    }
+
+    #[test]
+    fn describing_a_plan() {
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.write_file("README.md", "# myproject\n");
+        plan.run(Command::new("cargo").arg("new").path_arg(""));
+        plan.run(Command::new("go").arg("mod").current_dir(""));
+        plan.run_in_place(
+            Command::in_project("venv/bin/pip")
+                .arg("install")
+                .arg("ipython"),
+        );
+
+        assert_eq!(
+            plan.describe(Path::new("/tmp/myproject")),
+            vec![
+                "create directory /tmp/myproject",
+                "write file /tmp/myproject/README.md",
+                "run `cargo new /tmp/myproject`",
+                "run `go mod` in /tmp/myproject",
+                "run `/tmp/myproject/venv/bin/pip install ipython`",
+            ]
+        );
+    }
+
+    #[test]
+    fn adding_to_toml_arrays() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("Cargo.toml");
+        fs::write(&path, "[workspace]\nmembers = [\"crates/core\"]\n").unwrap();
+
+        add_to_toml_array(&path, "workspace.members", "crates/cli").unwrap();
+        add_to_toml_array(&path, "workspace.members", "crates/core").unwrap();
+        add_to_toml_array(&path, "workspace.exclude", "old").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[workspace]\nmembers = [\"crates/core\", \"crates/cli\"]\nexclude = [\"old\"]\n"
+        );
+    }
+
+    #[test]
+    fn changing_an_existing_project() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let mut plan = Plan::existing();
+        plan.write_file("notes.txt", "hello");
+        assert!(plan.check_target(&path).is_err());
+        assert!(plan.execute(&path).is_err());
+
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("README.md"), "# myproject\n").unwrap();
+        plan.execute(&path).unwrap();
+        assert_eq!(fs::read_to_string(path.join("notes.txt")).unwrap(), "hello");
+        assert!(path.join("README.md").is_file());
+    }
+
+    #[test]
+    fn missing_programs_are_reported() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.run(Command::new("mkproject-no-such-tool").arg("init"));
+
+        match plan.execute(&path) {
+            Err(MakeProjectError::Process(msg, 127)) => {
+                assert!(msg.contains("`mkproject-no-such-tool` was not found"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn running_hooks() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("hooked");
+
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        let hook = |command: &str| Command::new("sh").arg("-c").arg(command).current_dir("");
+        plan.run_before(hook("ls > before.txt").label("pre_create hook `ls`"));
+        plan.run_after(hook("ls > after.txt").label("post_create hook `ls`"));
+        assert_eq!(
+            plan.describe(&path),
+            vec![
+                format!("run pre_create hook `ls` in {}", temp_dir.path().display()),
+                format!("create directory {}", path.display()),
+                format!("run post_create hook `ls` in {}", path.display()),
+            ]
+        );
+
+        plan.execute(&path).unwrap();
+        // The project did not exist yet when the first hook ran
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("before.txt")).unwrap(),
+            "before.txt\n"
+        );
+        assert_eq!(
+            fs::read_to_string(path.join("after.txt")).unwrap(),
+            "after.txt\n"
+        );
+
+        // A failing hook after the project is created leaves it in place
+        let path = temp_dir.path().join("failing-hook");
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.run_after(hook("exit 1").label("post_create hook `exit 1`"));
+        match plan.execute(&path) {
+            Err(MakeProjectError::Process(msg, COMMAND_FAILED)) => {
+                assert!(msg.starts_with(&format!(
+                    "post_create hook `exit 1` in {} failed with exit code 1",
+                    path.display()
+                )))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn failing_programs_are_reported() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("failing");
+
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.run(
+            Command::new("sh")
+                .arg("-c")
+                .arg("echo 'no space left' >&2; exit 3"),
+        );
+
+        match plan.execute(&path) {
+            Err(MakeProjectError::Process(msg, COMMAND_FAILED)) => {
+                let (msg, log) = msg.split_once("\nThe full log is in ").unwrap();
+                assert_eq!(
+                    msg,
+                    "`sh -c echo 'no space left' >&2; exit 3` failed with exit code 3:\n\
+                     no space left"
+                );
+                let log = Path::new(log);
+                assert!(log.starts_with(env::temp_dir()));
+                assert!(log
+                    .parent()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("mkproject-failing."));
+                assert!(fs::read_to_string(log).unwrap().contains("no space left\n"));
+                fs::remove_dir_all(log.parent().unwrap()).unwrap();
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert!(!path.exists());
+
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.run(Command::new("sh").arg("-c").arg("kill -9 $$"));
+        match plan.execute(&path) {
+            Err(MakeProjectError::Process(msg, 137)) => {
+                assert!(msg.contains("` was killed by signal 9\n"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn logging_command_output() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        plan.run(Command::new("sh").arg("-c").arg("echo out; echo err >&2"));
+        plan.execute(&path).unwrap();
+
+        let log = fs::read_to_string(path.join(LOG_FILE)).unwrap();
+        assert!(log.starts_with(&format!("create directory {}\n", path.display())));
+        assert!(log.contains("run `sh -c echo out; echo err >&2`\n"));
+        assert!(log.contains("out\n"));
+        assert!(log.contains("err\n"));
+    }
+
+    #[test]
+    fn resolving_conflicts() {
+        let temp_dir = TempDir::new("mkproject-plan").unwrap();
+        let path = temp_dir.path();
+        fs::write(path.join("README.md"), "mine\n").unwrap();
+        fs::write(path.join("notes.txt"), "mine\n").unwrap();
+        fs::write(path.join("tool.toml"), "name = \"mine\"\n").unwrap();
+
+        let mut plan = Plan::existing();
+        plan.create_dir("");
+        plan.write_file("README.md", "# theirs\n");
+        plan.write_file("notes.txt", "theirs\n");
+        plan.write_file("new.txt", "new\n");
+        plan.run(
+            Command::new("sh")
+                .arg("-c")
+                .arg("test ! -e tool.toml && echo 'name = \"tool\"' > tool.toml")
+                .creates("tool.toml")
+                .current_dir(""),
+        );
+        plan.set_toml("tool.toml", "version", TomlValue::String("1".to_string()));
+
+        assert_eq!(
+            plan.conflicts(path),
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("notes.txt"),
+                PathBuf::from("tool.toml"),
+            ]
+        );
+        match plan.execute(path) {
+            Err(MakeProjectError::ArgumentError(msg)) => {
+                assert!(msg.starts_with("`README.md`, `notes.txt`, `tool.toml` already exist"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert!(plan.check_conflicts(path).is_err());
+        assert!(plan
+            .resolve(Path::new("README.md"), Conflict::Abort)
+            .is_err());
+
+        plan.resolve(Path::new("README.md"), Conflict::Skip)
+            .unwrap();
+        match plan.resolve(Path::new("tool.toml"), Conflict::Skip) {
+            Err(MakeProjectError::ArgumentError(msg)) => assert_eq!(
+                msg,
+                "`tool.toml` already exists and cannot be skipped, it is created by \
+                 `sh -c test ! -e tool.toml && echo 'name = \"tool\"' > tool.toml` \
+                 along with other files; overwrite it or move it out of the way"
+            ),
+            o => panic!("unexpected result: {:?}", o),
+        }
+        plan.resolve(Path::new("notes.txt"), Conflict::Overwrite)
+            .unwrap();
+        plan.resolve(Path::new("tool.toml"), Conflict::Overwrite)
+            .unwrap();
+        assert!(plan.conflicts(path).is_empty());
+        assert!(plan.check_conflicts(path).is_ok());
+        plan.execute(path).unwrap();
+
+        let read = |name| fs::read_to_string(path.join(name)).unwrap();
+        assert_eq!(read("README.md"), "mine\n");
+        assert_eq!(read("notes.txt"), "theirs\n");
+        assert_eq!(read("new.txt"), "new\n");
+        assert_eq!(read("tool.toml"), "name = \"tool\"\nversion = \"1\"\n");
+    }
+}
@@ -0,0 +1,377 @@
+//! Languages defined outside of mkproject.
+//!
+//! A plugin is a manifest describing the directories, files and commands that
+//! make up a new project. It is either a TOML file,
+//! `~/.config/mkproject/languages/<name>.toml`, or an executable called
+//! `mkproject-lang-<name>` on `PATH` which prints the same TOML when run as
+//! `mkproject-lang-<name> manifest`.
+//!
+//! ```toml
+//! description = "Zig projects built with `zig build`"
+//! directories = ["src"]
+//! gitignore = ["zig-out/", ".zig-cache/"]
+//!
+//! [[files]]
+//! path = "src/main.zig"
+//! contents = "// {{project_name}} by {{author}}\n"
+//!
+//! [[commands]]
+//! run = ["zig", "build"]
+//! # Run once the project is in its final location, defaults to false
+//! in_place = true
+//! ```
+//!
+//! Placeholders are replaced in file contents and command arguments, as they
+//! are in templates.
+
+use crate::config::config_dir;
+use crate::doctor::find_executable_in;
+use crate::plan::{Command, Plan};
+use crate::project::{create_readme, Metadata, Readme};
+use crate::template::Variables;
+use crate::{MakeProjectError, COMMAND_FAILED};
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use std::{env, fs, process};
+
+/// Prefix of plugin executables on `PATH`
+const EXECUTABLE_PREFIX: &str = "mkproject-lang-";
+
+/// A language provided by a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    name: String,
+    /// The manifest or executable the plugin was loaded from
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+/// What a plugin creates
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    description: Option<String>,
+    #[serde(default)]
+    directories: Vec<PathBuf>,
+    #[serde(default)]
+    files: Vec<FileSpec>,
+    #[serde(default)]
+    commands: Vec<CommandSpec>,
+    #[serde(default)]
+    gitignore: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileSpec {
+    path: PathBuf,
+    contents: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandSpec {
+    run: Vec<String>,
+    #[serde(default)]
+    in_place: bool,
+}
+
+/// `$XDG_CONFIG_HOME/mkproject/languages` or `~/.config/mkproject/languages`
+pub fn plugins_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("languages"))
+}
+
+impl Plugin {
+    /// Find the plugin called `name`, preferring a manifest in `plugins_dir`
+    /// to an executable on `PATH`
+    pub fn find(name: &str) -> Result<Option<Plugin>, MakeProjectError> {
+        Plugin::find_in(name, &env::var_os("PATH").unwrap_or_default())
+    }
+
+    /// Like `find`, looking for executables in `paths` rather than `PATH`
+    pub(crate) fn find_in(name: &str, paths: &OsStr) -> Result<Option<Plugin>, MakeProjectError> {
+        if !is_plugin_name(name) {
+            return Ok(None);
+        }
+
+        if let Some(dir) = plugins_dir() {
+            let path = dir.join(format!("{}.toml", name));
+            if path.is_file() {
+                let text = fs::read_to_string(&path)?;
+                return Plugin::parse(name, &path, &text).map(Some);
+            }
+        }
+
+        match find_executable_in(&format!("{}{}", EXECUTABLE_PREFIX, name), paths) {
+            Some(path) => Plugin::from_executable(name, &path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Names of every plugin in `plugins_dir` or on `PATH`, sorted
+    pub fn names() -> Vec<String> {
+        Plugin::names_in(&env::var_os("PATH").unwrap_or_default())
+    }
+
+    /// Like `names`, looking for executables in `paths` rather than `PATH`
+    pub(crate) fn names_in(paths: &OsStr) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some(dir) = plugins_dir() {
+            names.extend(
+                file_names(&dir)
+                    .into_iter()
+                    .filter_map(|name| name.strip_suffix(".toml").map(str::to_string)),
+            );
+        }
+        for dir in env::split_paths(paths) {
+            names.extend(
+                file_names(&dir)
+                    .into_iter()
+                    .filter_map(|name| name.strip_prefix(EXECUTABLE_PREFIX).map(str::to_string)),
+            );
+        }
+        names.retain(|name| is_plugin_name(name));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Load the manifest printed by the executable at `path`
+    fn from_executable(name: &str, path: &Path) -> Result<Plugin, MakeProjectError> {
+        let op = process::Command::new(path).arg("manifest").output()?;
+        if !op.status.success() {
+            return Err(MakeProjectError::Process(
+                format!(
+                    "`{} manifest` failed ({}): {}",
+                    path.display(),
+                    op.status,
+                    String::from_utf8_lossy(&op.stderr).trim_end()
+                ),
+                COMMAND_FAILED,
+            ));
+        }
+        Plugin::parse(name, path, &String::from_utf8_lossy(&op.stdout))
+    }
+
+    fn parse(name: &str, path: &Path, text: &str) -> Result<Plugin, MakeProjectError> {
+        let invalid = |msg: String| {
+            MakeProjectError::Config(format!("plugin `{}` ({}): {}", name, path.display(), msg))
+        };
+
+        let manifest: Manifest = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        let paths = manifest
+            .directories
+            .iter()
+            .chain(manifest.files.iter().map(|f| &f.path));
+        for path in paths {
+            if !is_relative(path) {
+                return Err(invalid(format!(
+                    "`{}` is not a path inside the project",
+                    path.display()
+                )));
+            }
+        }
+        if manifest.commands.iter().any(|c| c.run.is_empty()) {
+            return Err(invalid("a command has nothing to run".to_string()));
+        }
+
+        Ok(Plugin {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.manifest.description.as_deref()
+    }
+
+    /// Programs run by the plugin's commands
+    pub(crate) fn programs(&self) -> Vec<&str> {
+        let mut programs: Vec<_> = self
+            .manifest
+            .commands
+            .iter()
+            .map(|c| c.run[0].as_str())
+            .collect();
+        programs.sort();
+        programs.dedup();
+        programs
+    }
+
+    /// Entries for the `.gitignore` of a new project
+    pub(crate) fn ignored(&self) -> &[String] {
+        &self.manifest.gitignore
+    }
+
+    /// The steps creating a project described by the manifest
+    pub(crate) fn plan(&self, meta: &Metadata, vars: &Variables) -> Plan {
+        let mut plan = Plan::new();
+        plan.create_dir("");
+        create_readme(&mut plan, meta, Readme::default());
+        for dir in &self.manifest.directories {
+            plan.create_dir(dir);
+        }
+        for file in &self.manifest.files {
+            if let Some(parent) = file.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                plan.create_dir(parent);
+            }
+            plan.write_file(&file.path, vars.render(&file.contents));
+        }
+        for spec in &self.manifest.commands {
+            let cmd = Command::new(vars.render(&spec.run[0]))
+                .args(spec.run[1..].iter().map(|arg| vars.render(arg)))
+                .current_dir("");
+            if spec.in_place {
+                plan.run_in_place(cmd);
+            } else {
+                plan.run(cmd);
+            }
+        }
+        plan
+    }
+}
+
+/// Names that can be used in file names without surprises
+fn is_plugin_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_lowercase() || c.is_ascii_digit() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Whether `path` stays inside the directory it is relative to
+fn is_relative(path: &Path) -> bool {
+    path.components().all(|c| match c {
+        Component::Normal(_) | Component::CurDir => true,
+        Component::ParentDir | Component::RootDir | Component::Prefix(_) => false,
+    })
+}
+
+/// Names of the entries of `dir`, or none if it cannot be read
+fn file_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+    use crate::{Language, Options, ProjectBuilder, Vcs};
+    use tempdir::TempDir;
+
+    const MANIFEST: &str = r#"
+description = "Zig projects"
+directories = ["src"]
+gitignore = ["zig-out/"]
+
+[[files]]
+path = "build.zig"
+contents = "// {{project_name}} by {{author}}\n"
+
+[[commands]]
+run = ["zig", "init", "{{project_name}}"]
+
+[[commands]]
+run = ["zig", "build"]
+in_place = true
+"#;
+
+    #[test]
+    fn planning_a_plugin_project() {
+        let plugin = Plugin::parse("zig", Path::new("zig.toml"), MANIFEST).unwrap();
+        assert_eq!(plugin.description(), Some("Zig projects"));
+        assert_eq!(plugin.ignored(), ["zig-out/"]);
+
+        let mut vars = Variables::new("myproject");
+        vars.author = "Jo Bloggs".to_string();
+        let plan = plugin.plan(&Metadata::new("myproject".into()), &vars);
+        assert_eq!(
+            plan.describe(Path::new("/p")),
+            vec![
+                "create directory /p",
+                "write file /p/README.md",
+                "create directory /p/src",
+                "write file /p/build.zig",
+                "run `zig init myproject` in /p",
+                "run `zig build` in /p",
+            ]
+        );
+        assert_eq!(
+            planned_file(&plan, "build.zig"),
+            "// myproject by Jo Bloggs\n"
+        );
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let parse = |text| Plugin::parse("bad", Path::new("bad.toml"), text);
+        assert!(parse("unknown = 1").is_err());
+        assert!(parse("[[commands]]\nrun = []").is_err());
+        match parse("[[files]]\npath = \"../outside\"\ncontents = \"\"") {
+            Err(MakeProjectError::Config(msg)) => {
+                assert_eq!(
+                    msg,
+                    "plugin `bad` (bad.toml): `../outside` is not a path inside the project"
+                )
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn finding_executable_plugins() {
+        let stubs = StubTools::new();
+        let paths = stubs.path();
+        let plugin = Plugin::find_in("stub", &paths)
+            .unwrap()
+            .expect("stub plugin");
+        assert_eq!(plugin.name(), "stub");
+        assert!(plugin.path().ends_with("mkproject-lang-stub"));
+        assert_eq!(plugin.description(), Some("A stub language"));
+
+        assert_eq!(Plugin::find_in("no-such-language", &paths).unwrap(), None);
+        assert_eq!(Plugin::find_in("../stub", &paths).unwrap(), None);
+        assert!(Plugin::names_in(&paths).contains(&"stub".to_string()));
+    }
+
+    #[test]
+    fn creating_a_plugin_project() {
+        let stubs = StubTools::new();
+        let temp_dir = TempDir::new("mkproject-plugin").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let options = Options {
+            vcs: Some(Vcs::None),
+            ..Default::default()
+        };
+        let language = Language::parse("stub", &stubs.path()).unwrap();
+        ProjectBuilder::new(&path, language)
+            .options(options)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(path.join("main.stub")).unwrap(),
+            "myproject"
+        );
+        assert!(path.join("README.md").is_file());
+    }
+}
@@ -0,0 +1,707 @@
+//! Building a new project: the parts shared by every language, and the
+//! `ProjectBuilder` that puts them together with the language specific plan.
+
+use crate::c::{c_project_plan, BuildSystem};
+use crate::doctor::{language_tools, preflight, vcs_tools};
+use crate::go::go_project_plan;
+use crate::license::License;
+use crate::node::node_project_plan;
+use crate::options::{Options, Vcs};
+use crate::plan::{Command, Conflict, Plan, Progress, LOG_FILE};
+use crate::python::python_project_plan;
+use crate::rust::{rust_add_members_plan, rust_project_plan};
+use crate::template::{apply_template, find_template, templates_dir, Template, Variables};
+use crate::{Language, MakeProjectError};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::{env, process};
+
+/// Language specific parts of a generated README
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Readme {
+    /// Commands setting up a working copy of the project
+    pub(crate) install: Vec<String>,
+    /// Commands running or testing the project
+    pub(crate) usage: Vec<String>,
+    /// Package registry badge, as Markdown
+    pub(crate) badge: Option<String>,
+}
+
+/// Write the README. It only holds the project name, unless a description,
+/// license or badges were asked for.
+pub(crate) fn create_readme(plan: &mut Plan, meta: &Metadata, readme: Readme) {
+    plan.write_file("README.md", render_readme(meta, readme));
+}
+
+fn render_readme(meta: &Metadata, readme: Readme) -> String {
+    let project_name = meta
+        .name
+        .to_str()
+        .expect("path contains invalid UTF-8 data");
+    let mut contents = format!("# {}\n", project_name);
+    if meta.description.is_none() && meta.license.is_none() && !meta.badges {
+        return contents;
+    }
+
+    if meta.badges {
+        let mut badges: Vec<_> = readme.badge.into_iter().collect();
+        if let Some(license) = meta.license {
+            // Dashes separate the parts of a shields.io badge
+            badges.push(format!(
+                "[![License: {spdx}](https://img.shields.io/badge/license-{badge}-blue.svg)](LICENSE)",
+                spdx = license.spdx(),
+                badge = license.spdx().replace('-', "--"),
+            ));
+        }
+        if !badges.is_empty() {
+            contents.push_str(&format!("\n{}\n", badges.join("\n")));
+        }
+    }
+    if let Some(description) = &meta.description {
+        contents.push_str(&format!("\n{}\n", description));
+    }
+
+    let sections = [("Installation", readme.install), ("Usage", readme.usage)];
+    for (title, commands) in sections.iter() {
+        if !commands.is_empty() {
+            contents.push_str(&format!(
+                "\n## {}\n\n```sh\n{}\n```\n",
+                title,
+                commands.join("\n")
+            ));
+        }
+    }
+    if let Some(license) = meta.license {
+        contents.push_str(&format!(
+            "\n## License\n\nLicensed under the {} license, see [LICENSE](LICENSE).\n",
+            license.spdx()
+        ));
+    }
+    contents
+}
+
+/// Details of the project that are shared by every language
+#[derive(Debug, Default)]
+pub(crate) struct Metadata {
+    pub(crate) name: OsString,
+    pub(crate) author: Option<String>,
+    pub(crate) email: Option<String>,
+    pub(crate) license: Option<License>,
+    pub(crate) dependencies: Vec<String>,
+    pub(crate) dev_dependencies: Vec<String>,
+    pub(crate) description: Option<String>,
+    /// Whether to add badges to the README
+    pub(crate) badges: bool,
+    /// Whether the project is created inside an existing directory
+    pub(crate) existing: bool,
+}
+
+impl Metadata {
+    pub(crate) fn new(name: OsString) -> Metadata {
+        Metadata {
+            name,
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn compute_project_name(project_path: &Path) -> OsString {
+    let stub = project_path
+        .file_name()
+        .expect("no final path component given");
+
+    stub.to_os_string()
+}
+
+/// Author name from `git config user.name`, falling back to the login name,
+/// or none if neither is set
+pub fn default_author() -> Option<String> {
+    let from_git = process::Command::new("git")
+        .args(["config", "--get", "user.name"])
+        .output()
+        .ok()
+        .filter(|op| op.status.success())
+        .and_then(|op| String::from_utf8(op.stdout).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    from_git.or_else(|| {
+        env::var("USER")
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// Contents of the `.gitignore` for a new project
+fn gitignore(language: &Language, options: &Options, project_name: &OsStr) -> String {
+    let ignored = match language {
+        Language::Python => {
+            let env = options.python.env().manager().ignored();
+            env.iter()
+                .chain(&["__pycache__/", "*.egg-info/"])
+                .map(|entry| entry.to_string())
+                .collect()
+        }
+        Language::Rust => vec!["/target".to_string()],
+        Language::Go => {
+            // `go build` names the binary after the last module path element
+            let binary = match &options.go.module {
+                Some(module) => module.rsplit('/').next().unwrap_or(module).to_string(),
+                None => project_name.to_string_lossy().into_owned(),
+            };
+            vec![format!("/{}", binary), "*.test".to_string()]
+        }
+        Language::JavaScript => vec!["node_modules/".to_string()],
+        Language::TypeScript => vec!["node_modules/".to_string(), "dist/".to_string()],
+        Language::C | Language::Cpp => match options.c.build_system() {
+            BuildSystem::CMake => vec!["/build/".to_string()],
+            BuildSystem::Meson => vec!["/builddir/".to_string()],
+        },
+        Language::Plugin(plugin) => plugin.ignored().to_vec(),
+    };
+
+    let mut contents = ignored.join("\n");
+    contents.push('\n');
+    contents.push_str(LOG_FILE);
+    contents.push('\n');
+    contents
+}
+
+/// Initialise version control once all files have been generated
+fn vcs_plan(plan: &mut Plan, options: &Options) {
+    if options.vcs() == Vcs::None {
+        return;
+    }
+
+    plan.run(Command::new("git").arg("init").current_dir(""));
+    if let Some(message) = options.commit_message() {
+        plan.run(Command::new("git").arg("add").arg("-A").current_dir(""));
+
+        // Commit as the configured author, so that this also works on
+        // machines where git itself has no identity set up
+        let mut commit = Command::new("git");
+        if let (Some(author), Some(email)) = (&options.author, &options.email) {
+            commit = commit
+                .arg("-c")
+                .arg(format!("user.name={}", author))
+                .arg("-c")
+                .arg(format!("user.email={}", email));
+        }
+        plan.run(commit.arg("commit").arg("-m").arg(message).current_dir(""));
+    }
+}
+
+/// Builds a new project in a language, at a path
+#[derive(Debug)]
+pub struct ProjectBuilder {
+    path: PathBuf,
+    language: Language,
+    name: Option<String>,
+    options: Options,
+    progress: Progress,
+    init: bool,
+    on_conflict: Option<Conflict>,
+}
+
+impl ProjectBuilder {
+    pub fn new<P: Into<PathBuf>>(path: P, language: Language) -> ProjectBuilder {
+        ProjectBuilder {
+            path: path.into(),
+            language,
+            name: None,
+            options: Options::default(),
+            progress: Progress::Silent,
+            init: false,
+            on_conflict: None,
+        }
+    }
+
+    /// Name of the project, defaults to the last component of the path
+    pub fn name<S: Into<String>>(mut self, name: S) -> ProjectBuilder {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> ProjectBuilder {
+        self.options = options;
+        self
+    }
+
+    /// What to show while running commands, nothing by default
+    pub fn progress(mut self, progress: Progress) -> ProjectBuilder {
+        self.progress = progress;
+        self
+    }
+
+    /// Create the project inside the existing directory at the path, like
+    /// `cargo init`
+    pub fn init(mut self) -> ProjectBuilder {
+        self.init = true;
+        self
+    }
+
+    /// What to do about every file that `init` would create but which
+    /// already exists. Without this, such files are left in the plan's
+    /// `conflicts` and executing the plan fails.
+    pub fn on_conflict(mut self, conflict: Conflict) -> ProjectBuilder {
+        self.on_conflict = Some(conflict);
+        self
+    }
+
+    fn project_name(&self) -> Result<OsString, MakeProjectError> {
+        if let Some(name) = &self.name {
+            return Ok(OsString::from(name));
+        }
+        // Such as `.`, when initialising the current directory
+        if self.path.file_name().is_none() && self.path.is_dir() {
+            return Ok(compute_project_name(&self.path.canonicalize()?));
+        }
+        match self.path.file_name() {
+            Some(_) => Ok(compute_project_name(&self.path)),
+            None => Err(MakeProjectError::ArgumentError(format!(
+                "cannot name a project after `{}`",
+                self.path.display()
+            ))),
+        }
+    }
+
+    /// The path of the project, made absolute for hooks
+    fn absolute_path(&self) -> Result<PathBuf, MakeProjectError> {
+        if self.path.exists() {
+            return Ok(self.path.canonicalize()?);
+        }
+        Ok(env::current_dir()?.join(&self.path))
+    }
+
+    /// Check that every program the project needs is installed, reporting
+    /// all of the missing ones together
+    pub fn check_tools(&self) -> Result<(), MakeProjectError> {
+        let mut tools = language_tools(&self.language, &self.options);
+        tools.extend(vcs_tools(&self.options));
+        preflight(&tools)
+    }
+
+    /// The steps that `build` would take, without taking them
+    pub fn plan(&self) -> Result<Plan, MakeProjectError> {
+        let options = &self.options;
+        options.validate(Some(&self.language))?;
+
+        let project_name = self.project_name()?;
+        let mut meta = Metadata::new(project_name.clone());
+        meta.author = options.author.clone();
+        meta.email = options.email.clone();
+        meta.license = options.license;
+        meta.dependencies = options.deps.dependencies.clone();
+        meta.dev_dependencies = options.deps.dev_dependencies.clone();
+        meta.description = options.description.clone();
+        meta.badges = options.badges;
+        meta.existing = self.init;
+
+        // Adding to an existing workspace leaves the rest of it alone
+        let workspace = self.language == Language::Rust && options.rust.workspace;
+        if workspace && !self.init && self.path.exists() {
+            let unused = if options.template.is_some() {
+                Some("`--template`")
+            } else if options.commit_message().is_some() {
+                Some("an initial commit")
+            } else {
+                None
+            };
+            if let Some(unused) = unused {
+                return Err(MakeProjectError::ArgumentError(format!(
+                    "{} cannot be used when adding members to an existing workspace",
+                    unused
+                )));
+            }
+            let mut plan = rust_add_members_plan(&meta, &options.rust, &self.path)?;
+            self.add_hooks(&mut plan, &options.pre_create, &options.post_create)?;
+            plan.set_progress(self.progress);
+            return Ok(plan);
+        }
+
+        let mut vars = Variables::new(&project_name.to_string_lossy());
+        vars.author = options.author.clone().unwrap_or_default();
+        vars.email = options.email.clone().unwrap_or_default();
+        vars.description = options.description.clone().unwrap_or_default();
+        if let Some(license) = options.license {
+            vars.license = license.spdx().to_string();
+        }
+
+        let mut plan = match &self.language {
+            Language::Python => python_project_plan(&meta, &options.python)?,
+            Language::Rust => rust_project_plan(&meta, &options.rust)?,
+            Language::Go => go_project_plan(&meta, &options.go),
+            Language::JavaScript => node_project_plan(&meta, false, &options.node),
+            Language::TypeScript => node_project_plan(&meta, true, &options.node),
+            Language::C => c_project_plan(&meta, false, &options.c),
+            Language::Cpp => c_project_plan(&meta, true, &options.c),
+            Language::Plugin(plugin) => plugin.plan(&meta, &vars),
+        };
+
+        // Written before the template, so that templates can replace it
+        if options.vcs() == Vcs::Git {
+            plan.write_file(
+                ".gitignore",
+                gitignore(&self.language, options, &project_name),
+            );
+        }
+
+        if let Some(license) = options.license {
+            plan.write_file("LICENSE", license.text(&vars));
+        }
+
+        let mut pre_create = Vec::new();
+        let mut post_create = Vec::new();
+        if let Some(name) = &options.template {
+            let templates_dir = templates_dir().ok_or_else(|| {
+                MakeProjectError::ArgumentError("cannot find the home directory".to_string())
+            })?;
+            let template = Template::load(&find_template(&templates_dir, name)?)?;
+            apply_template(&mut plan, template.path(), &vars)?;
+            pre_create.extend_from_slice(template.pre_create());
+            post_create.extend_from_slice(template.post_create());
+        }
+        pre_create.extend_from_slice(&options.pre_create);
+        post_create.extend_from_slice(&options.post_create);
+
+        vcs_plan(&mut plan, options);
+
+        self.add_hooks(&mut plan, &pre_create, &post_create)?;
+        plan.set_progress(self.progress);
+
+        if self.init {
+            plan.set_existing();
+            plan.check_target(&self.path)?;
+            if let Some(conflict) = self.on_conflict {
+                for path in plan.conflicts(&self.path) {
+                    plan.resolve(&path, conflict)?;
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Run the `pre_create` and `post_create` hooks before and after the rest
+    /// of `plan`
+    fn add_hooks(
+        &self,
+        plan: &mut Plan,
+        pre_create: &[String],
+        post_create: &[String],
+    ) -> Result<(), MakeProjectError> {
+        let project_name = self.project_name()?;
+        let path = self.absolute_path()?;
+        let hook = |kind: &str, command: &String| {
+            Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .current_dir("")
+                .env("MKPROJECT_NAME", &project_name)
+                .env("MKPROJECT_LANGUAGE", self.language.as_str())
+                .env("MKPROJECT_PATH", &path)
+                .label(format!("{} hook `{}`", kind, command))
+        };
+        for command in pre_create {
+            plan.run_before(hook("pre_create", command));
+        }
+        for command in post_create {
+            plan.run_after(hook("post_create", command));
+        }
+        Ok(())
+    }
+
+    /// Create the project. Nothing is left behind if this fails, except when
+    /// initialising an existing directory.
+    pub fn build(self) -> Result<Project, MakeProjectError> {
+        self.check_tools()?;
+        self.plan()?.execute(&self.path)?;
+        Ok(Project {
+            name: self.project_name()?.to_string_lossy().into_owned(),
+            path: self.path,
+            language: self.language,
+        })
+    }
+}
+
+/// A project that has been created
+#[derive(Debug)]
+pub struct Project {
+    name: String,
+    path: PathBuf,
+    language: Language,
+}
+
+impl Project {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeOptions;
+    use crate::python::PythonOptions;
+    use crate::test_util::*;
+    use crate::COMMAND_FAILED;
+    use std::fs;
+    use structopt::StructOpt;
+    use tempdir::TempDir;
+
+    #[test]
+    fn ignoring_python_virtual_environments() {
+        let options = Options::default();
+        let ignored = gitignore(&Language::Python, &options, OsStr::new("p"));
+        assert!(ignored.lines().any(|line| line == "venv/"));
+
+        let options = Options::from_iter_safe(&["mkproject", "--python-env", "uv"]).unwrap();
+        let ignored = gitignore(&Language::Python, &options, OsStr::new("p"));
+        assert!(ignored.lines().any(|line| line == ".venv/"));
+        assert!(!ignored.lines().any(|line| line == "venv/"));
+    }
+
+    #[test]
+    fn license_metadata_for_other_languages() {
+        let mut meta = Metadata::new(OsString::from("myproject"));
+        meta.license = Some(License::Mit);
+
+        let plan = python_project_plan(&meta, &PythonOptions::default()).unwrap();
+        assert!(planned_file(&plan, "pyproject.toml").contains("license = \"MIT\"\n"));
+
+        let plan = node_project_plan(&meta, false, &NodeOptions { no_install: true });
+        let package: serde_json::Value =
+            serde_json::from_str(&planned_file(&plan, "package.json")).unwrap();
+        assert_eq!(package["license"], "MIT");
+    }
+
+    #[test]
+    fn description_metadata() {
+        let mut meta = Metadata::new(OsString::from("myproject"));
+        meta.description = Some("Does things".to_string());
+
+        let plan = python_project_plan(&meta, &PythonOptions::default()).unwrap();
+        let pyproject: toml::Value =
+            toml::from_str(&planned_file(&plan, "pyproject.toml")).unwrap();
+        assert_eq!(
+            pyproject["project"]["description"].as_str(),
+            Some("Does things")
+        );
+
+        let plan = node_project_plan(&meta, false, &NodeOptions { no_install: true });
+        let package: serde_json::Value =
+            serde_json::from_str(&planned_file(&plan, "package.json")).unwrap();
+        assert_eq!(package["description"], "Does things");
+    }
+
+    #[test]
+    fn rendering_readmes() {
+        let readme = Readme {
+            install: vec!["cargo install --path .".to_string()],
+            usage: vec!["cargo run".to_string()],
+            badge: Some("[![Crates.io](badge.svg)](crates)".to_string()),
+        };
+        let mut meta = Metadata::new(OsString::from("myproject"));
+        assert_eq!(render_readme(&meta, readme.clone()), "# myproject\n");
+
+        meta.license = Some(License::Apache2);
+        assert_eq!(
+            render_readme(&meta, readme.clone()),
+            "# myproject\n\
+             \n\
+             ## Installation\n\
+             \n\
+             ```sh\n\
+             cargo install --path .\n\
+             ```\n\
+             \n\
+             ## Usage\n\
+             \n\
+             ```sh\n\
+             cargo run\n\
+             ```\n\
+             \n\
+             ## License\n\
+             \n\
+             Licensed under the Apache-2.0 license, see [LICENSE](LICENSE).\n"
+        );
+
+        meta.description = Some("Does things".to_string());
+        meta.badges = true;
+        assert_eq!(
+            render_readme(&meta, readme),
+            "# myproject\n\
+             \n\
+             [![Crates.io](badge.svg)](crates)\n\
+             [![License: Apache-2.0](https://img.shields.io/badge/license-Apache--2.0-blue.svg)](LICENSE)\n\
+             \n\
+             Does things\n\
+             \n\
+             ## Installation\n\
+             \n\
+             ```sh\n\
+             cargo install --path .\n\
+             ```\n\
+             \n\
+             ## Usage\n\
+             \n\
+             ```sh\n\
+             cargo run\n\
+             ```\n\
+             \n\
+             ## License\n\
+             \n\
+             Licensed under the Apache-2.0 license, see [LICENSE](LICENSE).\n"
+        );
+
+        // Languages without their own sections only get the description
+        meta.badges = false;
+        meta.license = None;
+        assert_eq!(
+            render_readme(&meta, Readme::default()),
+            "# myproject\n\nDoes things\n"
+        );
+    }
+
+    #[test]
+    fn building_a_project_with_the_library() {
+        let temp_dir = TempDir::new("mkproject-builder").unwrap();
+        let path = temp_dir.path().join("some-dir");
+
+        let mut options = Options {
+            vcs: Some(Vcs::None),
+            license: Some(License::Mit),
+            ..Default::default()
+        };
+        options.c.build_system = Some(BuildSystem::Meson);
+        let project = ProjectBuilder::new(&path, Language::C)
+            .name("widget")
+            .options(options)
+            .build()
+            .unwrap();
+
+        assert_eq!(project.name(), "widget");
+        assert_eq!(project.path(), path.as_path());
+        assert_eq!(project.language(), &Language::C);
+        assert!(path.join("meson.build").is_file());
+        assert!(path.join("LICENSE").is_file());
+        assert!(!path.join(".git").exists());
+    }
+
+    #[test]
+    fn options_are_validated_by_the_builder() {
+        let mut options = Options::default();
+        options.node.no_install = true;
+        match ProjectBuilder::new("/tmp/p", Language::Rust)
+            .options(options)
+            .plan()
+        {
+            Err(MakeProjectError::ArgumentError(msg)) => assert!(msg.contains("--no-install")),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn initialising_an_existing_directory() {
+        let temp_dir = TempDir::new("mkproject-init").unwrap();
+        let path = temp_dir.path().join("widget");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("README.md"), "Hand written\n").unwrap();
+
+        let options = Options {
+            vcs: Some(Vcs::None),
+            ..Default::default()
+        };
+        let builder = ProjectBuilder::new(&path, Language::C)
+            .options(options.clone())
+            .init();
+        let plan = builder.plan().unwrap();
+        assert_eq!(plan.conflicts(&path), vec![PathBuf::from("README.md")]);
+        assert!(plan.execute(&path).is_err());
+        assert!(!path.join("CMakeLists.txt").exists());
+
+        ProjectBuilder::new(path.join("."), Language::C)
+            .options(options)
+            .init()
+            .on_conflict(Conflict::Skip)
+            .build()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(path.join("README.md")).unwrap(),
+            "Hand written\n"
+        );
+        let cmake = fs::read_to_string(path.join("CMakeLists.txt")).unwrap();
+        assert!(cmake.contains("project(widget"));
+    }
+
+    #[test]
+    fn skipping_files_created_by_commands() {
+        let temp_dir = TempDir::new("mkproject-init").unwrap();
+        let path = temp_dir.path().join("widget");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("Cargo.toml"), "[package]\n").unwrap();
+
+        let options = Options {
+            vcs: Some(Vcs::None),
+            license: Some(License::Mit),
+            ..Default::default()
+        };
+        let result = ProjectBuilder::new(&path, Language::Rust)
+            .options(options)
+            .init()
+            .on_conflict(Conflict::Skip)
+            .build();
+        match result {
+            Err(MakeProjectError::ArgumentError(msg)) => assert!(msg.starts_with(
+                "`Cargo.toml` already exists and cannot be skipped, it is created by `cargo init"
+            )),
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert_eq!(
+            fs::read_to_string(path.join("Cargo.toml")).unwrap(),
+            "[package]\n"
+        );
+        assert!(!path.join("src").exists());
+    }
+
+    #[test]
+    fn running_hooks() {
+        let temp_dir = TempDir::new("mkproject-hooks").unwrap();
+        let path = temp_dir.path().join("hooked");
+
+        let options = Options {
+            vcs: Some(Vcs::None),
+            pre_create: vec!["test ! -e hooked && touch pre-create-ran".to_string()],
+            post_create: vec![
+                "echo \"$MKPROJECT_NAME $MKPROJECT_LANGUAGE $MKPROJECT_PATH\" > hook.txt"
+                    .to_string(),
+                "exit 4".to_string(),
+            ],
+            ..Default::default()
+        };
+        let result = ProjectBuilder::new(&path, Language::C)
+            .options(options)
+            .build();
+        match result {
+            Err(MakeProjectError::Process(msg, COMMAND_FAILED)) => {
+                assert!(msg.starts_with("post_create hook `exit 4` in "));
+                assert!(msg.contains("failed with exit code 4"));
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+
+        assert!(temp_dir.path().join("pre-create-ran").exists());
+        assert_eq!(
+            fs::read_to_string(path.join("hook.txt")).unwrap(),
+            format!("hooked c {}\n", path.display())
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! Python projects: a `pyproject.toml` with a src layout, and an environment
+//! created by one of the managers in `python_env`.
+
+use crate::options::Flag;
+use crate::plan::{toml_str, toml_str_array, Plan};
+use crate::project::{create_readme, Metadata};
+use crate::python_env::{Interpreter, PythonEnv};
+use crate::MakeProjectError;
+use std::path::Path;
+use structopt::StructOpt;
+
+/// Options controlling the Python environment
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct PythonOptions {
+    /// Interpreter used to create the environment, either a version such as
+    /// 3.11 or the name of or path to an interpreter (python)
+    #[structopt(long = "python", raw(global = "true"))]
+    pub python: Option<String>,
+
+    /// Environment manager: venv, uv, poetry, pipenv, conda or none,
+    /// defaults to venv (python)
+    #[structopt(long = "python-env", raw(global = "true"))]
+    pub env: Option<PythonEnv>,
+}
+
+impl PythonOptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[
+        ("--python", |o| o.python.python.is_some()),
+        ("--python-env", |o| o.python.env.is_some()),
+    ];
+
+    pub fn interpreter(&self) -> &str {
+        self.python.as_deref().unwrap_or("python3")
+    }
+
+    pub fn env(&self) -> PythonEnv {
+        self.env.unwrap_or(PythonEnv::Venv)
+    }
+}
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Import name of the package for a Python project, e.g. `my_project` for
+/// `My-Project`
+fn python_package_name(project_name: &str) -> Result<String, MakeProjectError> {
+    let name: String = project_name
+        .chars()
+        .map(|c| match c {
+            '-' | '.' | ' ' => '_',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect();
+
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && !PYTHON_KEYWORDS.contains(&name.as_str());
+    if !valid {
+        return Err(MakeProjectError::ArgumentError(format!(
+            "`{}` cannot be used as a Python package name, as `{}` is not a valid identifier",
+            project_name, name
+        )));
+    }
+    Ok(name)
+}
+
+/// `pyproject.toml` for a new project, with `dev_dependencies` recorded in
+/// the `dev` dependency group
+fn pyproject(
+    meta: &Metadata,
+    name: &str,
+    python: &Interpreter,
+    dev_dependencies: &[String],
+) -> String {
+    let mut project = vec![
+        format!("name = {}", toml_str(name)),
+        "version = \"0.1.0\"".to_string(),
+        "readme = \"README.md\"".to_string(),
+        format!(
+            "requires-python = {}",
+            toml_str(&format!(">={}", python.minor_version()))
+        ),
+    ];
+    if let Some(description) = &meta.description {
+        project.push(format!("description = {}", toml_str(description)));
+    }
+    if let Some(license) = meta.license {
+        project.push(format!("license = {}", toml_str(license.spdx())));
+    }
+    if let Some(author) = &meta.author {
+        let author = match &meta.email {
+            Some(email) => format!("name = {}, email = {}", toml_str(author), toml_str(email)),
+            None => format!("name = {}", toml_str(author)),
+        };
+        project.push(format!("authors = [{{ {} }}]", author));
+    }
+    project.push(format!(
+        "dependencies = {}",
+        toml_str_array(&meta.dependencies)
+    ));
+
+    let mut contents = format!(
+        "[build-system]\n\
+         requires = [\"setuptools>=77\"]\n\
+         build-backend = \"setuptools.build_meta\"\n\
+         \n\
+         [project]\n\
+         {}\n",
+        project.join("\n")
+    );
+    if !dev_dependencies.is_empty() {
+        contents.push_str(&format!(
+            "\n[dependency-groups]\ndev = {}\n",
+            toml_str_array(dev_dependencies)
+        ));
+    }
+    contents
+}
+
+pub(crate) fn python_project_plan(
+    meta: &Metadata,
+    options: &PythonOptions,
+) -> Result<Plan, MakeProjectError> {
+    let name = meta.name.to_string_lossy();
+    let package = python_package_name(&name)?;
+    let package_dir = Path::new("src").join(&package);
+
+    // Checked before anything is created
+    let python = Interpreter::find(options.interpreter())?;
+
+    let manager = options.env().manager();
+    let dev_dependencies = &meta.dev_dependencies;
+    let in_pyproject: &[String] = match manager.requirements_file() {
+        Some(_) => &[],
+        None => dev_dependencies,
+    };
+
+    let mut plan = Plan::new();
+    plan.note(format!(
+        "Using Python {} ({})",
+        python.version, python.program
+    ));
+    plan.create_dir("");
+    plan.write_file(
+        "pyproject.toml",
+        pyproject(meta, &name, &python, in_pyproject),
+    );
+    plan.create_dir(&package_dir);
+    plan.write_file(package_dir.join("__init__.py"), "__version__ = \"0.1.0\"\n");
+    plan.create_dir("tests");
+    plan.write_file(
+        Path::new("tests").join(format!("test_{}.py", package)),
+        format!(
+            "import {package}\n\n\ndef test_version():\n    assert {package}.__version__ == \"0.1.0\"\n",
+            package = package
+        ),
+    );
+    let mut readme = manager.readme(&python);
+    readme.badge = Some(format!(
+        "[![PyPI](https://img.shields.io/pypi/v/{name}.svg)](https://pypi.org/project/{name}/)",
+        name = name
+    ));
+    create_readme(&mut plan, meta, readme);
+    if let Some(file) = manager.requirements_file() {
+        if !dev_dependencies.is_empty() {
+            plan.write_file(file, dev_dependencies.join("\n") + "\n");
+        }
+    }
+
+    manager.plan(&mut plan, &python, dev_dependencies);
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::compute_project_name;
+    use crate::test_util::*;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::process;
+    use tempdir::TempDir;
+
+    fn create_python_project(path: &Path, options: &PythonOptions) -> Result<(), MakeProjectError> {
+        let mut meta = Metadata::new(compute_project_name(path));
+        meta.dev_dependencies = vec!["ipython".to_string()];
+        python_project_plan(&meta, options)?.execute(path)
+    }
+
+    #[test]
+    fn creating_a_python_project() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        create_python_project(&path, &PythonOptions::default()).expect("creating a Python project");
+
+        assert!(path.join("venv").is_dir());
+        assert!(path.join("README.md").is_file());
+        assert!(path.join("pyproject.toml").is_file());
+        assert!(path
+            .join("src")
+            .join("myproject")
+            .join("__init__.py")
+            .is_file());
+
+        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
+        assert_eq!(readme_contents, "# myproject\n");
+
+        // Check that ipython is installed
+        assert!(path.join("venv").join("bin").join("ipython").is_file());
+    }
+
+    #[test]
+    fn failing_venv_creation_leaves_nothing_behind() {
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let python = temp_dir.path().join("python");
+        write_stub(&python, &format!("{}exit 1\n", PYTHON_STUB));
+
+        let options = PythonOptions {
+            python: Some(python.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        assert!(create_python_project(&path, &options).is_err());
+
+        assert_only_contains(temp_dir.path(), &["python"]);
+    }
+
+    #[test]
+    fn failing_dependency_install_leaves_nothing_behind() {
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let python = temp_dir.path().join("python");
+        // Creates a venv whose pip always fails
+        write_stub(
+            &python,
+            &format!(
+                "{}\
+                 mkdir -p \"$3/bin\"\n\
+                 printf '#!/bin/sh\\nexit 1\\n' > \"$3/bin/pip\"\n\
+                 chmod +x \"$3/bin/pip\"\n",
+                PYTHON_STUB
+            ),
+        );
+
+        let options = PythonOptions {
+            python: Some(python.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        assert!(create_python_project(&path, &options).is_err());
+
+        assert_only_contains(temp_dir.path(), &["python"]);
+
+        // A second attempt is not blocked by leftovers from the first
+        write_stub(&python, &format!("{}exit 0\n", PYTHON_STUB));
+        assert!(create_python_project(&path, &options).is_err());
+        assert_only_contains(temp_dir.path(), &["python"]);
+    }
+
+    #[test]
+    fn creating_a_python_project_with_uv() {
+        let stubs = StubTools::new();
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let options = PythonOptions {
+            env: Some(PythonEnv::Uv),
+            ..Default::default()
+        };
+        let mut plan =
+            python_project_plan(&Metadata::new(compute_project_name(&path)), &options).unwrap();
+        plan.env("PATH", stubs.path());
+        plan.execute(&path).expect("creating a Python project");
+
+        assert!(path.join(".venv").is_dir());
+        assert!(path.join("pyproject.toml").is_file());
+        assert_eq!(
+            fs::read_to_string(path.join("uv.log")).unwrap(),
+            "venv --python python3\nsync\n"
+        );
+    }
+
+    #[test]
+    fn missing_environment_managers_are_reported() {
+        if process::Command::new("pipenv").output().is_ok() {
+            eprintln!("`pipenv` is installed, skipping");
+            return;
+        }
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        let options = PythonOptions {
+            env: Some(PythonEnv::Pipenv),
+            ..Default::default()
+        };
+        match create_python_project(&path, &options) {
+            Err(MakeProjectError::Process(msg, _)) => {
+                assert!(msg.contains("`pipenv` was not found"))
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert_only_contains(temp_dir.path(), &[]);
+    }
+
+    #[test]
+    fn normalising_python_package_names() {
+        assert_eq!(python_package_name("myproject").unwrap(), "myproject");
+        assert_eq!(python_package_name("My-Project").unwrap(), "my_project");
+        assert_eq!(python_package_name("my.project").unwrap(), "my_project");
+
+        for invalid in &["1project", "my+project", "class", ""] {
+            match python_package_name(invalid) {
+                Err(MakeProjectError::ArgumentError(_)) => {}
+                o => panic!("unexpected result for {:?}: {:?}", invalid, o),
+            }
+        }
+    }
+
+    #[test]
+    fn planning_a_python_src_layout() {
+        let meta = Metadata {
+            name: OsString::from("My-Project"),
+            author: Some("Jo \"JB\" Bloggs".to_string()),
+            email: Some("jo@example.com".to_string()),
+            ..Default::default()
+        };
+        let plan = python_project_plan(&meta, &PythonOptions::default()).unwrap();
+        assert_eq!(plan.notes().len(), 1);
+        assert!(plan.notes()[0].starts_with("Using Python 3."));
+
+        let pyproject: toml::Value =
+            toml::from_str(&planned_file(&plan, "pyproject.toml")).unwrap();
+        assert_eq!(pyproject["project"]["name"].as_str(), Some("My-Project"));
+        assert_eq!(
+            pyproject["project"]["authors"][0]["name"].as_str(),
+            Some("Jo \"JB\" Bloggs")
+        );
+        assert_eq!(
+            pyproject["build-system"]["build-backend"].as_str(),
+            Some("setuptools.build_meta")
+        );
+
+        assert_eq!(
+            planned_file(&plan, "src/my_project/__init__.py"),
+            "__version__ = \"0.1.0\"\n"
+        );
+        assert!(planned_file(&plan, "tests/test_my_project.py").starts_with("import my_project\n"));
+    }
+
+    #[test]
+    fn recording_the_python_version() {
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let python = temp_dir.path().join("python");
+        write_stub(&python, PYTHON_STUB);
+
+        let options = PythonOptions {
+            python: Some(python.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        let plan = python_project_plan(&Metadata::new(OsString::from("p")), &options).unwrap();
+        let pyproject: toml::Value =
+            toml::from_str(&planned_file(&plan, "pyproject.toml")).unwrap();
+        assert_eq!(
+            pyproject["project"]["requires-python"].as_str(),
+            Some(">=3.12")
+        );
+    }
+
+    #[test]
+    fn missing_interpreters_are_reported_before_creating_anything() {
+        let temp_dir = TempDir::new("mkproject-python-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let python = temp_dir.path().join("no-such-python");
+
+        let options = PythonOptions {
+            python: Some(python.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        match create_python_project(&path, &options) {
+            Err(MakeProjectError::ArgumentError(msg)) => assert!(msg.contains("was not found")),
+            o => panic!("unexpected result: {:?}", o),
+        }
+        assert_only_contains(temp_dir.path(), &[]);
+    }
+
+    #[test]
+    fn recording_python_dependencies() {
+        let mut meta = Metadata::new(OsString::from("myproject"));
+        meta.dependencies = vec!["requests>=2".to_string()];
+        meta.dev_dependencies = vec!["pytest".to_string(), "black".to_string()];
+
+        // venv keeps development dependencies in requirements-dev.txt
+        let plan = python_project_plan(&meta, &PythonOptions::default()).unwrap();
+        let pyproject: toml::Value =
+            toml::from_str(&planned_file(&plan, "pyproject.toml")).unwrap();
+        assert_eq!(
+            pyproject["project"]["dependencies"],
+            toml::Value::try_from(vec!["requests>=2"]).unwrap()
+        );
+        assert!(pyproject.get("dependency-groups").is_none());
+        assert_eq!(
+            planned_file(&plan, "requirements-dev.txt"),
+            "pytest\nblack\n"
+        );
+
+        // Other managers read them from the `dev` dependency group
+        let options = PythonOptions {
+            env: Some(PythonEnv::Uv),
+            ..Default::default()
+        };
+        let plan = python_project_plan(&meta, &options).unwrap();
+        let pyproject: toml::Value =
+            toml::from_str(&planned_file(&plan, "pyproject.toml")).unwrap();
+        assert_eq!(
+            pyproject["dependency-groups"]["dev"],
+            toml::Value::try_from(vec!["pytest", "black"]).unwrap()
+        );
+        assert!(!plan
+            .describe(Path::new("/p"))
+            .contains(&"write file /p/requirements-dev.txt".to_string()));
+    }
+}
@@ -0,0 +1,450 @@
+//! Environment managers for Python projects. Each manager creates the
+//! environment for a new project and installs the project and its development
+//! dependencies into it, using its own tooling.
+
+use crate::plan::{Command, Plan};
+use crate::project::Readme;
+use crate::MakeProjectError;
+use std::fmt;
+use std::io;
+use std::process;
+use std::str::FromStr;
+
+/// Arguments making a Python interpreter print its full version
+pub(crate) const VERSION_ARGS: [&str; 2] =
+    ["-c", "import sys; print('%d.%d.%d' % sys.version_info[:3])"];
+
+/// A Python interpreter that has been checked to exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpreter {
+    pub program: String,
+    /// Full version, e.g. `3.11.4`
+    pub version: String,
+}
+
+impl Interpreter {
+    /// Find the interpreter for `--python`, which is either a version such as
+    /// `3.11`, or the name of or path to an interpreter
+    pub fn find(python: &str) -> Result<Interpreter, MakeProjectError> {
+        let program = Interpreter::program(python);
+        let op = process::Command::new(&program)
+            .args(VERSION_ARGS)
+            .output()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => MakeProjectError::ArgumentError(format!(
+                    "Python interpreter `{}` was not found",
+                    program
+                )),
+                _ => e.into(),
+            })?;
+
+        let version = String::from_utf8_lossy(&op.stdout).trim().to_string();
+        if !op.status.success() || version.split('.').count() != 3 {
+            return Err(MakeProjectError::ArgumentError(format!(
+                "`{}` is not a working Python interpreter",
+                program
+            )));
+        }
+        Ok(Interpreter { program, version })
+    }
+
+    /// The program run for `--python`, e.g. `python3.11` for `3.11`
+    pub fn program(python: &str) -> String {
+        if !python.is_empty() && python.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            format!("python{}", python)
+        } else {
+            python.to_string()
+        }
+    }
+
+    /// Major and minor version, e.g. `3.11`
+    pub fn minor_version(&self) -> &str {
+        match self.version.rfind('.') {
+            Some(end) => &self.version[..end],
+            None => &self.version,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PythonEnv {
+    Venv,
+    Uv,
+    Poetry,
+    Pipenv,
+    Conda,
+    None,
+}
+
+impl PythonEnv {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PythonEnv::Venv => "venv",
+            PythonEnv::Uv => "uv",
+            PythonEnv::Poetry => "poetry",
+            PythonEnv::Pipenv => "pipenv",
+            PythonEnv::Conda => "conda",
+            PythonEnv::None => "none",
+        }
+    }
+
+    /// The strategy that sets up this kind of environment
+    pub fn manager(self) -> &'static dyn EnvManager {
+        match self {
+            PythonEnv::Venv => &Venv,
+            PythonEnv::Uv => &Uv,
+            PythonEnv::Poetry => &Poetry,
+            PythonEnv::Pipenv => &Pipenv,
+            PythonEnv::Conda => &Conda,
+            PythonEnv::None => &NoEnv,
+        }
+    }
+}
+
+impl fmt::Display for PythonEnv {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PythonEnv {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "venv" => Ok(PythonEnv::Venv),
+            "uv" => Ok(PythonEnv::Uv),
+            "poetry" => Ok(PythonEnv::Poetry),
+            "pipenv" => Ok(PythonEnv::Pipenv),
+            "conda" => Ok(PythonEnv::Conda),
+            "none" => Ok(PythonEnv::None),
+            o => Err(MakeProjectError::ArgumentError(format!(
+                "unknown Python environment manager: `{}`, expected one of venv, uv, poetry, \
+                 pipenv, conda or none",
+                o
+            ))),
+        }
+    }
+}
+
+/// Creates and populates the environment of a new Python project
+pub trait EnvManager {
+    /// Paths inside the project that hold the environment, for `.gitignore`
+    fn ignored(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// File listing the development dependencies, for managers that do not
+    /// read them from the `dev` dependency group in `pyproject.toml`
+    fn requirements_file(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Add the steps creating the environment with `python` and installing
+    /// the project and `dev_packages` into it. Environments cannot be moved
+    /// once created, so these run after the project is in place.
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, dev_packages: &[String]);
+
+    /// How to set up and use the environment, for the README
+    fn readme(&self, python: &Interpreter) -> Readme;
+}
+
+fn commands(commands: &[&str]) -> Vec<String> {
+    commands.iter().map(|c| c.to_string()).collect()
+}
+
+/// `python -m venv venv` and pip
+struct Venv;
+
+impl EnvManager for Venv {
+    fn ignored(&self) -> &'static [&'static str] {
+        &["venv/"]
+    }
+
+    fn requirements_file(&self) -> Option<&'static str> {
+        Some("requirements-dev.txt")
+    }
+
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, dev_packages: &[String]) {
+        plan.run_in_place(
+            Command::new(&python.program)
+                .arg("-m")
+                .arg("venv")
+                .path_arg("venv"),
+        );
+        if !dev_packages.is_empty() {
+            plan.run_in_place(
+                Command::in_project("venv/bin/pip")
+                    .arg("install")
+                    .arg("-r")
+                    .path_arg("requirements-dev.txt"),
+            );
+        }
+        plan.run_in_place(
+            Command::in_project("venv/bin/pip")
+                .arg("install")
+                .arg("-e")
+                .path_arg(""),
+        );
+    }
+
+    fn readme(&self, python: &Interpreter) -> Readme {
+        Readme {
+            install: vec![
+                format!("{} -m venv venv", python.program),
+                "source venv/bin/activate".to_string(),
+                "pip install -e .".to_string(),
+            ],
+            usage: commands(&["source venv/bin/activate", "python -m pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+/// uv, with the environment in `.venv`
+struct Uv;
+
+impl EnvManager for Uv {
+    fn ignored(&self) -> &'static [&'static str] {
+        &[".venv/"]
+    }
+
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, _dev_packages: &[String]) {
+        plan.run_in_place(
+            Command::new("uv")
+                .arg("venv")
+                .arg("--python")
+                .arg(&python.program)
+                .current_dir(""),
+        );
+        // Installs the project and the `dev` dependency group
+        plan.run_in_place(Command::new("uv").arg("sync").current_dir(""));
+    }
+
+    fn readme(&self, _python: &Interpreter) -> Readme {
+        Readme {
+            install: commands(&["uv sync"]),
+            usage: commands(&["uv run pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+/// Poetry, which keeps its environments outside the project
+struct Poetry;
+
+impl EnvManager for Poetry {
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, _dev_packages: &[String]) {
+        plan.run_in_place(
+            Command::new("poetry")
+                .arg("env")
+                .arg("use")
+                .arg(&python.program)
+                .current_dir(""),
+        );
+        // Installs the project and the `dev` dependency group
+        plan.run_in_place(Command::new("poetry").arg("install").current_dir(""));
+    }
+
+    fn readme(&self, _python: &Interpreter) -> Readme {
+        Readme {
+            install: commands(&["poetry install"]),
+            usage: commands(&["poetry run pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+/// Pipenv, which records dependencies in a `Pipfile`
+struct Pipenv;
+
+impl EnvManager for Pipenv {
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, dev_packages: &[String]) {
+        plan.run_in_place(
+            Command::new("pipenv")
+                .arg("--python")
+                .arg(&python.program)
+                .current_dir(""),
+        );
+        if !dev_packages.is_empty() {
+            plan.run_in_place(
+                Command::new("pipenv")
+                    .arg("install")
+                    .arg("--dev")
+                    .args(dev_packages)
+                    .current_dir(""),
+            );
+        }
+        plan.run_in_place(
+            Command::new("pipenv")
+                .arg("install")
+                .arg("-e")
+                .arg(".")
+                .current_dir(""),
+        );
+    }
+
+    fn readme(&self, _python: &Interpreter) -> Readme {
+        Readme {
+            install: commands(&["pipenv install --dev", "pipenv install -e ."]),
+            usage: commands(&["pipenv run pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+/// A conda environment in `env`
+struct Conda;
+
+impl EnvManager for Conda {
+    fn ignored(&self) -> &'static [&'static str] {
+        &["env/"]
+    }
+
+    fn plan(&self, plan: &mut Plan, python: &Interpreter, dev_packages: &[String]) {
+        // conda installs its own interpreter, of the same version
+        plan.run_in_place(
+            Command::new("conda")
+                .arg("create")
+                .arg("--yes")
+                .arg("--prefix")
+                .path_arg("env")
+                .arg(format!("python={}", python.minor_version()))
+                .arg("pip"),
+        );
+
+        let pip = || {
+            Command::new("conda")
+                .arg("run")
+                .arg("--prefix")
+                .path_arg("env")
+                .arg("pip")
+                .arg("install")
+        };
+        if !dev_packages.is_empty() {
+            plan.run_in_place(pip().args(dev_packages));
+        }
+        plan.run_in_place(pip().arg("-e").path_arg(""));
+    }
+
+    fn readme(&self, python: &Interpreter) -> Readme {
+        Readme {
+            install: vec![
+                format!(
+                    "conda create --prefix ./env python={} pip",
+                    python.minor_version()
+                ),
+                "conda run --prefix ./env pip install -e .".to_string(),
+            ],
+            usage: commands(&["conda activate ./env", "python -m pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+/// No environment, for projects managed some other way
+struct NoEnv;
+
+impl EnvManager for NoEnv {
+    fn plan(&self, _plan: &mut Plan, _python: &Interpreter, _dev_packages: &[String]) {}
+
+    fn readme(&self, _python: &Interpreter) -> Readme {
+        Readme {
+            install: commands(&["pip install -e ."]),
+            usage: commands(&["python -m pytest"]),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn describe(env: PythonEnv) -> Vec<String> {
+        let mut plan = Plan::new();
+        let dev_packages = vec!["pytest".to_string(), "mypy".to_string()];
+        let python = Interpreter {
+            program: "python3.11".to_string(),
+            version: "3.11.4".to_string(),
+        };
+        env.manager().plan(&mut plan, &python, &dev_packages);
+        plan.describe(Path::new("/p"))
+    }
+
+    #[test]
+    fn finding_interpreters() {
+        let temp_dir = TempDir::new("mkproject-python").unwrap();
+        let python = temp_dir.path().join("python");
+        fs::write(&python, "#!/bin/sh\necho 3.11.4\n").unwrap();
+        fs::set_permissions(&python, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let found = Interpreter::find(python.to_str().unwrap()).unwrap();
+        assert_eq!(found.version, "3.11.4");
+        assert_eq!(found.minor_version(), "3.11");
+
+        match Interpreter::find("3.99") {
+            Err(MakeProjectError::ArgumentError(msg)) => {
+                assert_eq!(msg, "Python interpreter `python3.99` was not found")
+            }
+            o => panic!("unexpected result: {:?}", o),
+        }
+
+        fs::write(&python, "#!/bin/sh\nexit 1\n").unwrap();
+        assert!(Interpreter::find(python.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parsing_environment_managers() {
+        assert_eq!(PythonEnv::from_str("uv").unwrap(), PythonEnv::Uv);
+        assert_eq!(PythonEnv::from_str("none").unwrap(), PythonEnv::None);
+        assert!(PythonEnv::from_str("virtualenv").is_err());
+    }
+
+    #[test]
+    fn planning_each_environment_manager() {
+        assert_eq!(
+            describe(PythonEnv::Venv),
+            vec![
+                "run `python3.11 -m venv /p/venv`",
+                "run `/p/venv/bin/pip install -r /p/requirements-dev.txt`",
+                "run `/p/venv/bin/pip install -e /p`",
+            ]
+        );
+        assert_eq!(
+            describe(PythonEnv::Uv),
+            vec![
+                "run `uv venv --python python3.11` in /p",
+                "run `uv sync` in /p",
+            ]
+        );
+        assert_eq!(
+            describe(PythonEnv::Poetry),
+            vec![
+                "run `poetry env use python3.11` in /p",
+                "run `poetry install` in /p",
+            ]
+        );
+        assert_eq!(
+            describe(PythonEnv::Pipenv),
+            vec![
+                "run `pipenv --python python3.11` in /p",
+                "run `pipenv install --dev pytest mypy` in /p",
+                "run `pipenv install -e .` in /p",
+            ]
+        );
+        assert_eq!(
+            describe(PythonEnv::Conda),
+            vec![
+                "run `conda create --yes --prefix /p/env python=3.11 pip`",
+                "run `conda run --prefix /p/env pip install pytest mypy`",
+                "run `conda run --prefix /p/env pip install -e /p`",
+            ]
+        );
+        assert!(describe(PythonEnv::None).is_empty());
+    }
+}
@@ -0,0 +1,517 @@
+//! Rust projects, created with `cargo new`, either as a single package or as
+//! a workspace of packages under `crates/`.
+
+use crate::options::Flag;
+use crate::plan::{toml_str, Command, Plan, TomlValue};
+use crate::project::{create_readme, Metadata, Readme};
+use crate::MakeProjectError;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Edition {
+    E2015,
+    E2018,
+    E2021,
+    E2024,
+}
+
+impl Edition {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Edition::E2015 => "2015",
+            Edition::E2018 => "2018",
+            Edition::E2021 => "2021",
+            Edition::E2024 => "2024",
+        }
+    }
+}
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Edition {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2015" => Ok(Edition::E2015),
+            "2018" => Ok(Edition::E2018),
+            "2021" => Ok(Edition::E2021),
+            "2024" => Ok(Edition::E2024),
+            o => Err(MakeProjectError::ArgumentError(format!(
+                "unknown Rust edition: `{}`",
+                o
+            ))),
+        }
+    }
+}
+
+/// A package in a Rust workspace, given as `name`, `name:lib` or `name:bin`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub lib: bool,
+}
+
+impl WorkspaceMember {
+    /// Location of the member, relative to the workspace root
+    pub fn path(&self) -> PathBuf {
+        Path::new("crates").join(&self.name)
+    }
+}
+
+impl FromStr for WorkspaceMember {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, lib) = match s.rsplit_once(':') {
+            Some((name, "lib")) => (name, true),
+            Some((name, "bin")) => (name, false),
+            Some(_) => {
+                return Err(MakeProjectError::ArgumentError(format!(
+                    "invalid workspace member: `{}`, expected `name`, `name:lib` or `name:bin`",
+                    s
+                )))
+            }
+            None => (s, true),
+        };
+
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(MakeProjectError::ArgumentError(format!(
+                "invalid workspace member name: `{}`",
+                name
+            )));
+        }
+        Ok(WorkspaceMember {
+            name: name.to_string(),
+            lib,
+        })
+    }
+}
+
+/// Options passed through to `cargo new`
+#[derive(Debug, Default, Clone, StructOpt)]
+pub struct RustOptions {
+    /// Create a library crate instead of a binary (rust)
+    #[structopt(long = "lib", raw(global = "true"))]
+    pub lib: bool,
+
+    /// Rust edition to use: 2015, 2018, 2021 or 2024 (rust)
+    #[structopt(long = "edition", raw(global = "true"))]
+    pub edition: Option<Edition>,
+
+    /// Package name, if different from the directory name (rust)
+    #[structopt(long = "name", raw(global = "true"))]
+    pub name: Option<String>,
+
+    /// Create a workspace, or add members to an existing one (rust)
+    #[structopt(long = "workspace", raw(global = "true"))]
+    pub workspace: bool,
+
+    /// Workspace member to create under crates/, as `name` for a library or
+    /// `name:bin` for a binary, may be repeated (rust)
+    #[structopt(long = "member", number_of_values = 1, raw(global = "true"))]
+    pub members: Vec<WorkspaceMember>,
+}
+
+impl RustOptions {
+    pub(crate) const FLAGS: &'static [Flag] = &[
+        ("--lib", |o| o.rust.lib),
+        ("--edition", |o| o.rust.edition.is_some()),
+        ("--name", |o| o.rust.name.is_some()),
+        ("--workspace", |o| o.rust.workspace),
+        ("--member", |o| !o.rust.members.is_empty()),
+    ];
+
+    /// Reject options that do not make sense together
+    pub(crate) fn validate(&self) -> Result<(), MakeProjectError> {
+        if self.workspace {
+            for (given, flag) in &[(self.lib, "--lib"), (self.name.is_some(), "--name")] {
+                if *given {
+                    return Err(MakeProjectError::ArgumentError(format!(
+                        "`{}` cannot be used with `--workspace`, use `--member` instead",
+                        flag
+                    )));
+                }
+            }
+        } else if !self.members.is_empty() {
+            return Err(MakeProjectError::ArgumentError(
+                "`--member` can only be used with `--workspace`".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Split a `name@version` dependency into its `Cargo.toml` key and version
+/// requirement. The version is required, as crates.io does not accept
+/// wildcard dependencies.
+fn crate_dependency(spec: &str) -> Result<(&str, &str), MakeProjectError> {
+    let (name, version) = spec.split_once('@').unwrap_or((spec, ""));
+
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name || version.is_empty() {
+        return Err(MakeProjectError::ArgumentError(format!(
+            "invalid crate dependency: `{}`, expected `name@version`, e.g. `serde@1.0`",
+            spec
+        )));
+    }
+    Ok((name, version))
+}
+
+/// `cargo new`, or `cargo init` for an existing directory, for a package in
+/// `dir`, relative to the project root
+fn cargo_new(
+    options: &RustOptions,
+    existing: bool,
+    lib: bool,
+    name: &OsStr,
+    dir: &Path,
+) -> Command {
+    let subcommand = if existing { "init" } else { "new" };
+    let mut cmd = Command::new("cargo")
+        .arg(subcommand)
+        .creates(dir.join("Cargo.toml"));
+    cmd = cmd.arg(if lib { "--lib" } else { "--bin" });
+    if let Some(edition) = options.edition {
+        cmd = cmd.arg("--edition").arg(edition.as_str());
+    }
+    // Version control is set up the same way for every language, afterwards.
+    // The project is built in a staging directory whose name is not a valid
+    // package name, so the name is always passed explicitly.
+    cmd.arg("--vcs")
+        .arg("none")
+        .arg("--name")
+        .arg(name)
+        .path_arg(dir)
+}
+
+/// Create a package with `cargo new`, then fill in its license and
+/// dependencies
+fn rust_package_plan(
+    plan: &mut Plan,
+    meta: &Metadata,
+    cargo_new: Command,
+    dir: &Path,
+) -> Result<(), MakeProjectError> {
+    let manifest = dir.join("Cargo.toml");
+    plan.run(cargo_new);
+    if let Some(description) = &meta.description {
+        let description = TomlValue::String(description.clone());
+        plan.set_toml(&manifest, "package.description", description);
+    }
+    if let Some(license) = meta.license {
+        let license = TomlValue::String(license.spdx().to_string());
+        plan.set_toml(&manifest, "package.license", license);
+    }
+    let tables = [
+        ("dependencies", &meta.dependencies),
+        ("dev-dependencies", &meta.dev_dependencies),
+    ];
+    for (table, dependencies) in tables.iter() {
+        for spec in dependencies.iter() {
+            let (name, version) = crate_dependency(spec)?;
+            let version = TomlValue::String(version.to_string());
+            plan.set_toml(&manifest, &format!("{}.{}", table, name), version);
+        }
+    }
+    Ok(())
+}
+
+fn rust_member_plan(
+    plan: &mut Plan,
+    meta: &Metadata,
+    options: &RustOptions,
+    member: &WorkspaceMember,
+) -> Result<(), MakeProjectError> {
+    let dir = member.path();
+    let cmd = cargo_new(options, false, member.lib, OsStr::new(&member.name), &dir);
+    rust_package_plan(plan, meta, cmd, &dir)
+}
+
+pub(crate) fn rust_project_plan(
+    meta: &Metadata,
+    options: &RustOptions,
+) -> Result<Plan, MakeProjectError> {
+    let mut plan = Plan::new();
+    let readme = if options.workspace {
+        let members: Vec<_> = options
+            .members
+            .iter()
+            .map(|m| toml_str(&m.path().to_string_lossy()))
+            .collect();
+        plan.create_dir("");
+        plan.write_file(
+            "Cargo.toml",
+            format!(
+                "[workspace]\nresolver = \"2\"\nmembers = [{}]\n",
+                members.join(", ")
+            ),
+        );
+        plan.create_dir("crates");
+        for member in &options.members {
+            rust_member_plan(&mut plan, meta, options, member)?;
+        }
+        Readme {
+            install: vec!["cargo build".to_string()],
+            usage: vec!["cargo test --workspace".to_string()],
+            ..Default::default()
+        }
+    } else {
+        let name = match &options.name {
+            Some(name) => OsStr::new(name),
+            None => &meta.name,
+        };
+        let cmd = cargo_new(options, meta.existing, options.lib, name, Path::new(""));
+        rust_package_plan(&mut plan, meta, cmd, Path::new(""))?;
+
+        let name = name.to_string_lossy();
+        let (install, usage) = if options.lib {
+            (format!("cargo add {}", name), "cargo test".to_string())
+        } else {
+            (
+                "cargo install --path .".to_string(),
+                "cargo run".to_string(),
+            )
+        };
+        Readme {
+            install: vec![install],
+            usage: vec![usage],
+            badge: Some(format!(
+                "[![Crates.io](https://img.shields.io/crates/v/{name}.svg)](https://crates.io/crates/{name})",
+                name = name
+            )),
+        }
+    };
+    create_readme(&mut plan, meta, readme);
+    Ok(plan)
+}
+
+/// Add the members given with `--member` to the existing workspace at `path`
+pub(crate) fn rust_add_members_plan(
+    meta: &Metadata,
+    options: &RustOptions,
+    path: &Path,
+) -> Result<Plan, MakeProjectError> {
+    let manifest_path = path.join("Cargo.toml");
+    let manifest: Option<toml::Value> = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|text| toml::from_str(&text).ok());
+    let workspace = manifest.as_ref().and_then(|m| m.get("workspace"));
+    let existing = match workspace {
+        Some(workspace) => workspace
+            .get("members")
+            .and_then(|m| m.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        None => {
+            return Err(MakeProjectError::ArgumentError(format!(
+                "`{}` already exists and is not a Cargo workspace",
+                path.display()
+            )))
+        }
+    };
+
+    if options.members.is_empty() {
+        return Err(MakeProjectError::ArgumentError(
+            "no members to add to the workspace, pass `--member`".to_string(),
+        ));
+    }
+
+    let mut plan = Plan::existing();
+    for member in &options.members {
+        let member_path = member.path().to_string_lossy().into_owned();
+        if existing.iter().any(|m| m.as_str() == Some(&member_path)) {
+            return Err(MakeProjectError::ArgumentError(format!(
+                "`{}` is already a member of the workspace",
+                member_path
+            )));
+        }
+        rust_member_plan(&mut plan, meta, options, member)?;
+        plan.add_to_toml_array("Cargo.toml", "workspace.members", &member_path);
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Conflict;
+    use crate::project::compute_project_name;
+    use crate::template::{apply_template, Variables};
+    use crate::test_util::*;
+    use tempdir::TempDir;
+
+    fn create_rust_project(path: &Path, options: &RustOptions) -> Result<(), MakeProjectError> {
+        rust_project_plan(&Metadata::new(compute_project_name(path)), options)?.execute(path)
+    }
+
+    #[test]
+    fn parsing_editions() {
+        assert_eq!(Edition::from_str("2018").unwrap(), Edition::E2018);
+        assert!(Edition::from_str("2017").is_err());
+    }
+
+    #[test]
+    fn invalid_crate_dependencies_are_rejected() {
+        assert_eq!(crate_dependency("serde@^1").unwrap(), ("serde", "^1"));
+        for invalid in &["", "serde", "@1", "serde@", "serde.json", "serde json"] {
+            assert!(crate_dependency(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn parsing_workspace_members() {
+        let core = WorkspaceMember::from_str("core").unwrap();
+        assert_eq!(core.name, "core");
+        assert!(core.lib);
+        assert_eq!(core.path(), Path::new("crates/core"));
+
+        assert!(!WorkspaceMember::from_str("cli:bin").unwrap().lib);
+        assert!(WorkspaceMember::from_str("cli:lib").unwrap().lib);
+        for invalid in &["", ":bin", "cli:exe", "a/b"] {
+            assert!(WorkspaceMember::from_str(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn layering_a_template_over_a_rust_project() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let template = temp_dir.path().join("mytemplate");
+        fs::create_dir_all(template.join("src")).unwrap();
+        fs::write(
+            template.join("README.md"),
+            "# {{project_name}} by {{author}}\n",
+        )
+        .unwrap();
+        fs::write(template.join("src").join("{{project_name}}.rs"), "").unwrap();
+
+        let path = temp_dir.path().join("myproject");
+        let project_name = compute_project_name(&path);
+        let mut plan =
+            rust_project_plan(&Metadata::new(project_name), &RustOptions::default()).unwrap();
+        let mut vars = Variables::new("myproject");
+        vars.author = "Jo Bloggs".to_string();
+        apply_template(&mut plan, &template, &vars).unwrap();
+        plan.execute(&path).expect("creating Rust project");
+
+        assert!(path.join("Cargo.toml").is_file());
+        assert!(path.join("src").join("main.rs").is_file());
+        assert!(path.join("src").join("myproject.rs").is_file());
+
+        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
+        assert_eq!(readme_contents, "# myproject by Jo Bloggs\n");
+    }
+
+    #[test]
+    fn creating_a_rust_library_with_options() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let options = RustOptions {
+            lib: true,
+            name: Some("otherproject".to_string()),
+            ..Default::default()
+        };
+
+        create_rust_project(&path, &options).expect("creating Rust project");
+
+        assert!(path.join("src").join("lib.rs").is_file());
+        assert!(!path.join(".git").exists());
+
+        let manifest = fs::read_to_string(path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"otherproject\""));
+    }
+
+    #[test]
+    fn creating_a_rust_project() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+
+        create_rust_project(&path, &RustOptions::default()).expect("creating Rust project");
+
+        assert!(path.join("Cargo.toml").is_file());
+        assert!(path.join("src").is_dir());
+        assert!(path.join("src").join("main.rs").is_file());
+        assert!(path.join("README.md").is_file());
+
+        let readme_contents = fs::read_to_string(path.join("README.md")).unwrap();
+        assert_eq!(readme_contents, "# myproject\n");
+    }
+
+    #[test]
+    fn failing_cargo_new_leaves_nothing_behind() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        let options = RustOptions {
+            name: Some("1-not-a-valid-name".to_string()),
+            ..Default::default()
+        };
+
+        assert!(create_rust_project(&path, &options).is_err());
+
+        assert_only_contains(temp_dir.path(), &[]);
+    }
+
+    #[test]
+    fn existing_destination_is_left_alone() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("notes.txt"), "important").unwrap();
+
+        match create_rust_project(&path, &RustOptions::default()) {
+            Err(MakeProjectError::ArgumentError(_)) => {}
+            o => panic!("unexpected result: {:?}", o),
+        }
+
+        assert_only_contains(&path, &["notes.txt"]);
+    }
+
+    #[test]
+    fn initialising_an_existing_directory() {
+        let temp_dir = TempDir::new("mkproject-rust-project").unwrap();
+        let path = temp_dir.path().join("myproject");
+        fs::create_dir_all(path.join("src")).unwrap();
+        fs::write(path.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(path.join("Cargo.toml"), "# hand written\n").unwrap();
+
+        let mut meta = Metadata::new(compute_project_name(&path));
+        meta.existing = true;
+        let mut plan = rust_project_plan(&meta, &RustOptions::default()).unwrap();
+        plan.set_existing();
+        assert_eq!(
+            plan.describe(&path)[0],
+            format!(
+                "run `cargo init --bin --vcs none --name myproject {}`",
+                path.display()
+            )
+        );
+        assert_eq!(plan.conflicts(&path), vec![PathBuf::from("Cargo.toml")]);
+
+        plan.resolve(Path::new("Cargo.toml"), Conflict::Overwrite)
+            .unwrap();
+        plan.execute(&path).expect("initialising Rust project");
+
+        let manifest = fs::read_to_string(path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"myproject\""));
+        assert_eq!(
+            fs::read_to_string(path.join("src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(path.join("README.md").is_file());
+    }
+}
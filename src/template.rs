@@ -0,0 +1,376 @@
+//! User defined project templates.
+//!
+//! A template is a directory under `~/.config/mkproject/templates/<name>/`
+//! whose contents are copied into the new project, replacing any generated
+//! files such as the README. Placeholders such as `{{project_name}}` or
+//! `{{description}}` are replaced in both file contents and file names.
+//!
+//! An optional `mkproject.toml` at the top of the template describes it, and
+//! is not copied:
+//!
+//! ```toml
+//! description = "Python service with CI and a Dockerfile"
+//! # Shell commands run before and after the project is created, before the
+//! # hooks from the config file
+//! pre_create = []
+//! post_create = ["pre-commit install"]
+//! ```
+
+use crate::config::config_dir;
+use crate::plan::Plan;
+use crate::MakeProjectError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+/// Values substituted for `{{name}}` placeholders
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables {
+    pub project_name: String,
+    pub author: String,
+    pub email: String,
+    pub license: String,
+    pub year: String,
+    pub description: String,
+}
+
+impl Variables {
+    /// Variables for the given project, with the current year filled in
+    pub fn new(project_name: &str) -> Variables {
+        Variables {
+            project_name: project_name.to_string(),
+            year: current_year().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        match key {
+            "project_name" => Some(&self.project_name),
+            "author" => Some(&self.author),
+            "email" => Some(&self.email),
+            "license" => Some(&self.license),
+            "year" => Some(&self.year),
+            "description" => Some(&self.description),
+            _ => None,
+        }
+    }
+
+    /// Replace every known `{{name}}` placeholder in `text`. Unknown
+    /// placeholders are left alone, so templates can contain other templating
+    /// syntax (e.g. `${{ matrix.os }}` in CI configuration).
+    pub fn render(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let end = match after.find("}}") {
+                Some(end) => end,
+                None => break,
+            };
+
+            out.push_str(&rest[..start]);
+            match self.lookup(after[..end].trim()) {
+                Some(value) => out.push_str(value),
+                None => out.push_str(&rest[start..start + 2 + end + 2]),
+            }
+            rest = &after[end + 2..];
+        }
+
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Directory holding user templates, `$XDG_CONFIG_HOME/mkproject/templates`
+/// or `~/.config/mkproject/templates`
+pub fn templates_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("templates"))
+}
+
+/// File describing a template, kept out of new projects
+const MANIFEST: &str = "mkproject.toml";
+
+/// A template in `templates_dir`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    name: String,
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    description: Option<String>,
+    #[serde(default)]
+    pre_create: Vec<String>,
+    #[serde(default)]
+    post_create: Vec<String>,
+}
+
+impl Template {
+    /// Load the template at `path`, reading its `mkproject.toml` if it has one
+    pub fn load(path: &Path) -> Result<Template, MakeProjectError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let manifest_path = path.join(MANIFEST);
+        let manifest = if manifest_path.is_file() {
+            let text = fs::read_to_string(&manifest_path)?;
+            toml::from_str(&text).map_err(|e| {
+                MakeProjectError::Config(format!("{}: {}", manifest_path.display(), e))
+            })?
+        } else {
+            Manifest::default()
+        };
+        Ok(Template {
+            name,
+            path: path.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Every template in `templates_dir`, sorted by name
+    pub fn all(templates_dir: &Path) -> Result<Vec<Template>, MakeProjectError> {
+        let entries = match fs::read_dir(templates_dir) {
+            Ok(entries) => entries.collect::<Result<Vec<_>, _>>()?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut templates = entries
+            .iter()
+            .filter(|e| e.path().is_dir())
+            .map(|e| Template::load(&e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.manifest.description.as_deref()
+    }
+
+    pub fn pre_create(&self) -> &[String] {
+        &self.manifest.pre_create
+    }
+
+    pub fn post_create(&self) -> &[String] {
+        &self.manifest.post_create
+    }
+}
+
+/// Find the template called `name` inside `templates_dir`
+pub fn find_template(templates_dir: &Path, name: &str) -> Result<PathBuf, MakeProjectError> {
+    let path = templates_dir.join(name);
+    if !path.is_dir() {
+        return Err(MakeProjectError::ArgumentError(format!(
+            "template `{}` not found in {}",
+            name,
+            templates_dir.display()
+        )));
+    }
+    Ok(path)
+}
+
+/// Add the contents of the template at `template` to the plan, rendering
+/// placeholders in file names and in the contents of text files
+pub fn apply_template(
+    plan: &mut Plan,
+    template: &Path,
+    vars: &Variables,
+) -> Result<(), MakeProjectError> {
+    walk(plan, template, Path::new(""), vars)
+}
+
+fn walk(plan: &mut Plan, dir: &Path, rel: &Path, vars: &Variables) -> Result<(), MakeProjectError> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        if rel.as_os_str().is_empty() && name == MANIFEST {
+            continue;
+        }
+        let name = match name.to_str() {
+            Some(name) => vars.render(name),
+            None => {
+                return Err(MakeProjectError::ArgumentError(format!(
+                    "template file name is not valid UTF-8: {:?}",
+                    entry.path()
+                )))
+            }
+        };
+        let target = rel.join(name);
+
+        if entry.file_type()?.is_dir() {
+            plan.create_dir(target.clone());
+            walk(plan, &entry.path(), &target, vars)?;
+        } else {
+            let contents = fs::read(entry.path())?;
+            // Binary files are copied unchanged
+            let contents = match String::from_utf8(contents) {
+                Ok(text) => vars.render(&text).into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+            plan.write_file(target, contents);
+        }
+    }
+    Ok(())
+}
+
+fn current_year() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    year_from_days(secs.div_euclid(86_400))
+}
+
+/// Civil year of the given number of days since 1970-01-01, following Howard
+/// Hinnant's `civil_from_days` algorithm
+fn year_from_days(days: i64) -> i64 {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    let year = yoe + era * 400;
+    if month <= 2 {
+        year + 1
+    } else {
+        year
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Action;
+    use tempdir::TempDir;
+
+    fn vars() -> Variables {
+        Variables {
+            project_name: "myproject".to_string(),
+            author: "Jo Bloggs".to_string(),
+            year: "2019".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rendering_placeholders() {
+        assert_eq!(
+            vars().render("# {{project_name}} by {{ author }}, {{year}}"),
+            "# myproject by Jo Bloggs, 2019"
+        );
+
+        let vars = Variables {
+            description: "Does things".to_string(),
+            ..vars()
+        };
+        assert_eq!(vars.render("{{description}}"), "Does things");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        let text = "os: ${{ matrix.os }} {{project_name}} {{";
+        assert_eq!(vars().render(text), "os: ${{ matrix.os }} myproject {{");
+    }
+
+    #[test]
+    fn computing_years() {
+        assert_eq!(year_from_days(0), 1970);
+        assert_eq!(year_from_days(364), 1970);
+        assert_eq!(year_from_days(365), 1971);
+        // 2020-12-31 and 2021-01-01
+        assert_eq!(year_from_days(18_627), 2020);
+        assert_eq!(year_from_days(18_628), 2021);
+    }
+
+    #[test]
+    fn applying_a_template() {
+        let temp_dir = TempDir::new("mkproject-template").unwrap();
+        let template = temp_dir.path().join("mytemplate");
+        fs::create_dir_all(template.join("{{project_name}}")).unwrap();
+        fs::write(
+            template.join("{{project_name}}").join("notes.txt"),
+            "{{author}}",
+        )
+        .unwrap();
+        fs::write(template.join("logo.bin"), [0xff, 0xfe, b'{', b'{']).unwrap();
+
+        let mut plan = Plan::new();
+        apply_template(&mut plan, &template, &vars()).unwrap();
+
+        assert_eq!(
+            plan.describe(Path::new("/p")),
+            vec![
+                "write file /p/logo.bin",
+                "create directory /p/myproject",
+                "write file /p/myproject/notes.txt",
+            ]
+        );
+
+        let contents: Vec<_> = plan
+            .actions()
+            .filter_map(|a| match a {
+                Action::WriteFile(_, contents) => Some(contents.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(contents[0], vec![0xff, 0xfe, b'{', b'{']);
+        assert_eq!(contents[1], b"Jo Bloggs".to_vec());
+    }
+
+    #[test]
+    fn describing_templates() {
+        let temp_dir = TempDir::new("mkproject-template").unwrap();
+        fs::create_dir_all(temp_dir.path().join("plain")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("described")).unwrap();
+        fs::write(
+            temp_dir.path().join("described").join(MANIFEST),
+            "description = \"With CI\"\npost_create = [\"make setup\"]",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("described").join("ci.yml"), "").unwrap();
+
+        let templates = Template::all(temp_dir.path()).unwrap();
+        let names: Vec<_> = templates.iter().map(|t| t.name()).collect();
+        assert_eq!(names, ["described", "plain"]);
+        assert_eq!(templates[0].description(), Some("With CI"));
+        assert_eq!(templates[0].post_create(), ["make setup"]);
+        assert_eq!(templates[1].description(), None);
+        assert!(templates[1].post_create().is_empty());
+
+        // The manifest is not part of the project
+        let mut plan = Plan::new();
+        apply_template(&mut plan, templates[0].path(), &vars()).unwrap();
+        assert_eq!(plan.describe(Path::new("/p")), vec!["write file /p/ci.yml"]);
+
+        assert_eq!(
+            Template::all(&temp_dir.path().join("nope")).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn missing_templates() {
+        let temp_dir = TempDir::new("mkproject-template").unwrap();
+        assert!(find_template(temp_dir.path(), "nope").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Helpers shared by the tests of every module.
+
+use crate::plan::{Action, Plan};
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::{env, process};
+use tempdir::TempDir;
+
+/// Start of a stand-in Python interpreter, which answers the version check
+pub const PYTHON_STUB: &str = "#!/bin/sh\nif [ \"$1\" = -c ]; then echo 3.12.1; exit 0; fi\n";
+
+pub fn write_stub(path: &Path, script: &str) {
+    fs::write(path, script).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Whether `tool` can be run, for tests that need an optional toolchain
+pub fn have_tool(tool: &str) -> bool {
+    let found = process::Command::new(tool).arg("version").output().is_ok();
+    if !found {
+        eprintln!("`{}` not found, skipping", tool);
+    }
+    found
+}
+
+/// Contents of a file the plan would write
+pub fn planned_file(plan: &Plan, path: &str) -> String {
+    plan.actions()
+        .find_map(|a| match a {
+            Action::WriteFile(p, contents) if p == Path::new(path) => {
+                Some(String::from_utf8(contents.clone()).unwrap())
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("{} is not written", path))
+}
+
+pub fn assert_only_contains(dir: &Path, names: &[&str]) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, names);
+}
+
+/// Stand-ins for optional tools, and a language plugin, in a directory that
+/// is removed when they are dropped. The uv stub logs its arguments to `uv.log`
+/// in the directory it is run from.
+pub struct StubTools {
+    dir: TempDir,
+}
+
+impl StubTools {
+    pub fn new() -> StubTools {
+        let dir = TempDir::new("mkproject-stubs").unwrap();
+        write_stub(
+            &dir.path().join("uv"),
+            "#!/bin/sh\n\
+             echo \"$@\" >> uv.log\n\
+             if [ \"$1\" = venv ]; then mkdir .venv; fi\n",
+        );
+        write_stub(
+            &dir.path().join("mkproject-lang-stub"),
+            "#!/bin/sh\n\
+             cat <<'EOF'\n\
+             description = \"A stub language\"\n\
+             \n\
+             [[files]]\n\
+             path = \"main.stub\"\n\
+             contents = \"{{project_name}}\"\n\
+             EOF\n",
+        );
+        StubTools { dir }
+    }
+
+    /// `PATH` with the stubs at the front, to give to the code under test
+    /// rather than changing the environment of every test
+    pub fn path(&self) -> OsString {
+        let path = env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![self.dir.path().to_path_buf()];
+        paths.extend(env::split_paths(&path));
+        env::join_paths(paths).unwrap()
+    }
+}
@@ -0,0 +1,354 @@
+//! Interactive prompts for the options of a new project, used when
+//! `mkproject` is run without a language or path.
+
+use crate::Opt;
+use mkproject::config::Config;
+use mkproject::{Conflict, Language, License, MakeProjectError};
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub struct Wizard<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+    pub fn new(input: R, output: W) -> Wizard<R, W> {
+        Wizard { input, output }
+    }
+
+    /// Ask for the path, language and options that were not given on the
+    /// command line. Unanswered questions are left to the config file and
+    /// the usual defaults.
+    pub fn ask(&mut self, opts: &mut Opt, config: &Config) -> Result<(), MakeProjectError> {
+        if opts.path.is_none() {
+            let name = loop {
+                let name = self.prompt("Project name", None)?;
+                if !name.is_empty() {
+                    break name;
+                }
+            };
+            opts.path = Some(PathBuf::from(name));
+        }
+
+        if opts.language.is_none() {
+            let languages: Vec<_> = Language::ALL.iter().map(|l| l.as_str()).collect();
+            let default = config.language.as_deref();
+            opts.language = loop {
+                let language = self.choose("Language", &languages, default)?;
+                if language.is_some() || default.is_some() {
+                    break language;
+                }
+            };
+        }
+        // The language from the configuration file when its default was
+        // accepted, which decides the questions below
+        let language = opts
+            .language
+            .clone()
+            .or_else(|| config.language.as_deref()?.parse().ok());
+
+        let options = &mut opts.options;
+        if options.license.is_none() {
+            let licenses: Vec<_> = License::ALL.iter().map(|l| l.spdx()).collect();
+            let default = config.license.as_deref().or(Some("none"));
+            let license: Option<Choice<License>> =
+                self.choose("License", &with_none(&licenses), default)?;
+            options.license = license.and_then(Choice::into_option);
+        }
+        if options.vcs.is_none() {
+            let default = config.vcs.as_deref().or(Some("git"));
+            options.vcs = self.choose("Version control", &["git", "none"], default)?;
+        }
+
+        match language {
+            Some(Language::Rust) => {
+                if !options.rust.lib && !options.rust.workspace {
+                    let default = config.rust.lib.unwrap_or(false);
+                    options.rust.lib = self.confirm("Create a library?", default)?;
+                }
+                if options.rust.edition.is_none() {
+                    let default = config.rust.edition.as_deref().or(Some("cargo's default"));
+                    options.rust.edition =
+                        self.choose("Edition", &["2015", "2018", "2021", "2024"], default)?;
+                }
+            }
+            Some(Language::Python) => {
+                if options.python.python.is_none() {
+                    let default = config.python.python.as_deref().or(Some("python3"));
+                    let python = self.prompt("Python interpreter or version", default)?;
+                    if !python.is_empty() {
+                        options.python.python = Some(python);
+                    }
+                }
+                if options.python.env.is_none() {
+                    let default = config.python.env.as_deref().or(Some("venv"));
+                    let managers = ["venv", "uv", "poetry", "pipenv", "conda", "none"];
+                    options.python.env = self.choose("Environment manager", &managers, default)?;
+                }
+            }
+            Some(Language::Go) if options.go.module.is_none() => {
+                let module = self.prompt("Module path", Some("the project name"))?;
+                if !module.is_empty() {
+                    options.go.module = Some(module);
+                }
+            }
+            Some(Language::JavaScript) | Some(Language::TypeScript) if !options.node.no_install => {
+                let default = config.node.install.unwrap_or(true);
+                options.node.no_install = !self.confirm("Run `npm install`?", default)?;
+            }
+            Some(Language::C) | Some(Language::Cpp) if options.c.build_system.is_none() => {
+                let default = config.c.build_system.as_deref().or(Some("cmake"));
+                options.c.build_system =
+                    self.choose("Build system", &["cmake", "meson"], default)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Show the project that is about to be created and ask whether to go
+    /// ahead
+    pub fn confirm_summary(&mut self, opts: &Opt) -> Result<bool, MakeProjectError> {
+        let options = &opts.options;
+        let mut summary = vec![
+            ("path", display(&opts.path.as_ref().map(|p| p.display()))),
+            ("language", display(&opts.language)),
+            ("author", display(&options.author)),
+            ("license", display(&options.license)),
+            ("version control", options.vcs().to_string()),
+        ];
+        match opts.language {
+            Some(Language::Rust) => {
+                let kind = if options.rust.lib {
+                    "library"
+                } else {
+                    "binary"
+                };
+                summary.push(("crate", kind.to_string()));
+                summary.push(("edition", display(&options.rust.edition)));
+            }
+            Some(Language::Python) => {
+                summary.push(("interpreter", options.python.interpreter().to_string()));
+                summary.push(("environment", options.python.env().to_string()));
+            }
+            Some(Language::Go) => summary.push(("module", display(&options.go.module))),
+            Some(Language::JavaScript) | Some(Language::TypeScript) => {
+                let install = if options.node.no_install { "no" } else { "yes" };
+                summary.push(("npm install", install.to_string()));
+            }
+            Some(Language::C) | Some(Language::Cpp) => {
+                summary.push(("build system", options.c.build_system().to_string()));
+            }
+            _ => {}
+        }
+        if !options.deps.dependencies.is_empty() {
+            summary.push(("dependencies", options.deps.dependencies.join(", ")));
+        }
+        if !options.deps.dev_dependencies.is_empty() {
+            summary.push(("dev dependencies", options.deps.dev_dependencies.join(", ")));
+        }
+
+        writeln!(self.output)?;
+        for (key, value) in summary {
+            writeln!(self.output, "  {:<18}{}", format!("{}:", key), value)?;
+        }
+        writeln!(self.output)?;
+        self.confirm("Create this project?", true)
+    }
+
+    /// Ask what to do about `path`, which already exists
+    pub fn resolve_conflict(&mut self, path: &Path) -> Result<Conflict, MakeProjectError> {
+        let question = format!("`{}` already exists", path.display());
+        let choices = ["skip", "overwrite", "abort"];
+        let conflict = self.choose(&question, &choices, Some("abort"))?;
+        Ok(conflict.unwrap_or(Conflict::Abort))
+    }
+
+    /// Ask a question, returning the trimmed answer, which is empty if the
+    /// default was accepted
+    fn prompt(
+        &mut self,
+        question: &str,
+        default: Option<&str>,
+    ) -> Result<String, MakeProjectError> {
+        match default {
+            Some(default) => write!(self.output, "{} [{}]: ", question, default)?,
+            None => write!(self.output, "{}: ", question)?,
+        }
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            return Err(MakeProjectError::ArgumentError(
+                "no answer given, pass `--no-input` to run without prompts".to_string(),
+            ));
+        }
+        Ok(answer.trim().to_string())
+    }
+
+    /// Ask for one of `choices`, asking again until the answer can be parsed.
+    /// Accepting the default gives `None`.
+    fn choose<T>(
+        &mut self,
+        question: &str,
+        choices: &[&str],
+        default: Option<&str>,
+    ) -> Result<Option<T>, MakeProjectError>
+    where
+        T: FromStr<Err = MakeProjectError>,
+    {
+        let question = format!("{} ({})", question, choices.join(", "));
+        loop {
+            let answer = self.prompt(&question, default)?;
+            if answer.is_empty() {
+                return Ok(None);
+            }
+            match answer.parse() {
+                Ok(value) => return Ok(Some(value)),
+                Err(e) => writeln!(self.output, "{}", e)?,
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool, MakeProjectError> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let answer = self.prompt(&format!("{} [{}]", question, hint), None)?;
+            match answer.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => writeln!(self.output, "Please answer yes or no")?,
+            }
+        }
+    }
+}
+
+/// An answer that may be `none`
+enum Choice<T> {
+    Some(T),
+    None,
+}
+
+impl<T> Choice<T> {
+    fn into_option(self) -> Option<T> {
+        match self {
+            Choice::Some(value) => Some(value),
+            Choice::None => None,
+        }
+    }
+}
+
+impl<T: FromStr<Err = MakeProjectError>> FromStr for Choice<T> {
+    type Err = MakeProjectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Choice::None),
+            s => s.parse().map(Choice::Some),
+        }
+    }
+}
+
+fn with_none<'a>(choices: &[&'a str]) -> Vec<&'a str> {
+    let mut choices = choices.to_vec();
+    choices.push("none");
+    choices
+}
+
+fn display<T: Display>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "default".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkproject::{BuildSystem, Edition};
+    use std::io::Cursor;
+    use structopt::StructOpt;
+
+    fn run_wizard(args: &[&str], answers: &str) -> (Opt, bool, String) {
+        let mut opts = Opt::from_iter_safe(args).unwrap();
+        let mut output = Vec::new();
+        let mut wizard = Wizard::new(Cursor::new(answers.as_bytes()), &mut output);
+        wizard.ask(&mut opts, &Config::default()).unwrap();
+        let confirmed = wizard.confirm_summary(&opts).unwrap();
+        (opts, confirmed, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn answering_every_question() {
+        let (opts, confirmed, output) =
+            run_wizard(&["mkproject"], "myproject\nrust\nMIT\n\ny\n2021\n\n");
+
+        assert!(confirmed);
+        assert_eq!(opts.path, Some(PathBuf::from("myproject")));
+        assert_eq!(opts.language, Some(Language::Rust));
+        assert_eq!(opts.options.license, Some(License::Mit));
+        assert_eq!(opts.options.vcs, None);
+        assert!(opts.options.rust.lib);
+        assert_eq!(opts.options.rust.edition, Some(Edition::E2021));
+        assert!(output.contains("Language (python, rust, go, javascript, typescript, c, cpp): "));
+        assert!(output.contains("  crate:            library\n"));
+        assert!(output.contains("Create this project? [Y/n]: "));
+    }
+
+    #[test]
+    fn invalid_answers_are_asked_again() {
+        let (opts, _, output) = run_wizard(
+            &["mkproject", "--vcs", "none"],
+            "\nproj\ncobol\ngo\nWTFPL\nnone\n\nn\n",
+        );
+
+        assert_eq!(opts.path, Some(PathBuf::from("proj")));
+        assert_eq!(opts.language, Some(Language::Go));
+        assert_eq!(opts.options.license, None);
+        assert!(output.contains("parsing model from given command: `cobol`"));
+        assert!(output.contains("unknown license: `WTFPL`"));
+        assert!(!output.contains("Version control"));
+    }
+
+    #[test]
+    fn accepting_the_configured_language() {
+        let config = Config {
+            language: Some("c".to_string()),
+            ..Default::default()
+        };
+        let mut opts = Opt::from_iter_safe(&["mkproject", "proj"]).unwrap();
+        let mut output = Vec::new();
+        let mut wizard = Wizard::new(Cursor::new(&b"\n\n\nmeson\n"[..]), &mut output);
+        wizard.ask(&mut opts, &config).unwrap();
+
+        assert_eq!(opts.language, None);
+        assert_eq!(opts.options.c.build_system, Some(BuildSystem::Meson));
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains("Language (python, rust, go, javascript, typescript, c, cpp) [c]: ")
+        );
+
+        opts.merge_config(&config).unwrap();
+        assert_eq!(opts.language, Some(Language::C));
+    }
+
+    #[test]
+    fn declining_the_summary() {
+        let (_, confirmed, _) = run_wizard(&["mkproject", "-l", "c", "proj"], "\n\n\nno\n");
+        assert!(!confirmed);
+    }
+
+    #[test]
+    fn running_out_of_input() {
+        let mut opts = Opt::from_iter_safe(&["mkproject"]).unwrap();
+        let mut wizard = Wizard::new(Cursor::new(&b""[..]), Vec::new());
+        match wizard.ask(&mut opts, &Config::default()) {
+            Err(MakeProjectError::ArgumentError(msg)) => assert!(msg.contains("--no-input")),
+            o => panic!("unexpected result: {:?}", o),
+        }
+    }
+}